@@ -0,0 +1,78 @@
+//! `read_fields` against one `SpField::call` per field, on an object with 20 `int` fields.
+//! run with `cargo bench --bench read_fields`, it needs a jdk like the integration tests
+#[path = "../tests/common/mod.rs"]
+mod common;
+
+use jni::signature::{Primitive, ReturnType};
+use jni_sp_util::{SpField, SpType, read_fields};
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+const FIELDS: usize = 20;
+const ROUNDS: u32 = 20_000;
+
+fn per_round(elapsed: Duration) -> f64 {
+    elapsed.as_nanos() as f64 / ROUNDS as f64
+}
+
+fn main() {
+    common::with_jvm(|env| {
+        let class = common::define(env, "Wide");
+        let fields: Vec<SpField> = (0..FIELDS)
+            .map(|i| {
+                let field = SpField::new(i as i32, &format!("f{}", i), &SpType::Int);
+                field.init(env, &class).unwrap();
+                field
+            })
+            .collect();
+        let fields: Vec<&SpField> = fields.iter().collect();
+        let rets = vec![ReturnType::Primitive(Primitive::Int); FIELDS];
+        let wide = env.new_object(&class, "()V", &[]).unwrap();
+
+        let single_round = |env: &mut jni::JNIEnv| {
+            let mut sum = 0;
+            for field in &fields {
+                sum += field
+                    .call(env, &wide, rets[0].clone())
+                    .unwrap()
+                    .i()
+                    .unwrap();
+            }
+            sum
+        };
+        let batch_round = |env: &mut jni::JNIEnv| {
+            let values = read_fields(env, &wide, &fields, &rets).unwrap();
+            values
+                .into_iter()
+                .map(|value| value.i().unwrap())
+                .sum::<i32>()
+        };
+
+        // warm up both paths before timing them
+        for _ in 0..ROUNDS / 10 {
+            black_box(single_round(env) + batch_round(env));
+        }
+
+        let start = Instant::now();
+        let mut sum = 0;
+        for _ in 0..ROUNDS {
+            sum += single_round(env);
+        }
+        let single = start.elapsed();
+
+        let start = Instant::now();
+        for _ in 0..ROUNDS {
+            sum += batch_round(env);
+        }
+        let batch = start.elapsed();
+
+        black_box(sum);
+        println!("{} int fields, {} rounds", FIELDS, ROUNDS);
+        println!(
+            "SpField::call x{}: {:>10.0} ns/round",
+            FIELDS,
+            per_round(single)
+        );
+        println!("read_fields:       {:>10.0} ns/round", per_round(batch));
+    });
+}
@@ -0,0 +1,297 @@
+use jni::{
+    JNIEnv,
+    objects::{
+        AsJArrayRaw, AutoElements, AutoLocal, GlobalRef, JBooleanArray, JByteArray, JByteBuffer,
+        JDoubleArray, JIntArray, JLongArray, JObject, JObjectArray, JPrimitiveArray, ReleaseMode,
+        TypeArray,
+    },
+    sys::{jboolean, jsize},
+};
+use std::marker::PhantomData;
+use std::ops::{Deref, DerefMut};
+
+use crate::convert::{bool_to_jboolean, jboolean_to_bool};
+use crate::error::{Result, anyhow};
+use crate::point::{Point, to_ptr, to_status};
+
+pub fn jbyte_array_into_boxed_slice(env: &JNIEnv, array: &JByteArray) -> Result<Box<[u8]>> {
+    let bytes = env.convert_byte_array(array)?;
+    Ok(bytes.into_boxed_slice())
+}
+
+/// what to do with a `null` array
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum NullPolicy {
+    /// read `null` as an empty array
+    #[default]
+    Empty,
+    /// fail on `null`
+    Error,
+}
+
+/// copy a java `byte[]` into a `Vec<u8>`, a `null` array is handled by `null`
+pub fn jbyte_array_to_vec(env: &JNIEnv, array: &JByteArray, null: NullPolicy) -> Result<Vec<u8>> {
+    if array.is_null() {
+        return match null {
+            NullPolicy::Empty => Ok(Vec::new()),
+            NullPolicy::Error => Err(anyhow!("byte array is null")),
+        };
+    }
+    Ok(env.convert_byte_array(array)?)
+}
+
+/// copy a java `byte[]` into a rust owned `Box<[u8]>`,
+/// the point holds a `Box<[u8]>`: `to_status::<Box<[u8]>>(point)`
+pub fn jbyte_array_into_point(env: &JNIEnv, array: &JByteArray) -> Result<Point> {
+    let bytes = jbyte_array_into_boxed_slice(env, array)?;
+    Ok(to_ptr(bytes))
+}
+
+/// take back the bytes of `jbyte_array_into_point` as a new java `byte[]`, the point is freed
+pub fn point_into_jbyte_array<'local>(
+    env: &JNIEnv<'local>,
+    p: Point,
+) -> Result<JByteArray<'local>> {
+    let bytes = to_status::<Box<[u8]>>(p)?;
+    let array = env.byte_array_from_slice(&bytes)?;
+    Ok(array)
+}
+
+/// `start..start + len` must be inside the array
+fn check_region<'local>(
+    env: &JNIEnv,
+    array: &impl AsJArrayRaw<'local>,
+    start: usize,
+    len: usize,
+) -> Result<jsize> {
+    let array_len = env.get_array_length(array)? as usize;
+    match start.checked_add(len) {
+        Some(end) if end <= array_len => Ok(start as jsize),
+        _ => Err(anyhow!(
+            "array region out of bounds: {}..{} of length {}",
+            start,
+            start.saturating_add(len),
+            array_len
+        )),
+    }
+}
+
+/// write `values` into a caller provided `int[]` (an out-parameter) from `start`
+pub fn write_int_array(
+    env: &JNIEnv,
+    array: &JIntArray,
+    start: usize,
+    values: &[i32],
+) -> Result<()> {
+    let start = check_region(env, array, start, values.len())?;
+    env.set_int_array_region(array, start, values)?;
+    Ok(())
+}
+
+/// write `values` into a caller provided `long[]` (an out-parameter) from `start`
+pub fn write_long_array(
+    env: &JNIEnv,
+    array: &JLongArray,
+    start: usize,
+    values: &[i64],
+) -> Result<()> {
+    let start = check_region(env, array, start, values.len())?;
+    env.set_long_array_region(array, start, values)?;
+    Ok(())
+}
+
+/// write `values` into a caller provided `Object[]` (an out-parameter) from `start`
+pub fn write_object_array(
+    env: &JNIEnv,
+    array: &JObjectArray,
+    start: usize,
+    values: &[&JObject],
+) -> Result<()> {
+    let start = check_region(env, array, start, values.len())?;
+    for (i, value) in values.iter().enumerate() {
+        env.set_object_array_element(array, start + i as jsize, value)?;
+    }
+    Ok(())
+}
+
+/// every element of a java `Object[]` as a `GlobalRef`, so it outlives the local frame.
+/// a `null` array or element is an error
+pub fn object_array_to_globals(env: &mut JNIEnv, array: &JObjectArray) -> Result<Vec<GlobalRef>> {
+    if array.is_null() {
+        return Err(anyhow!("object array is null"));
+    }
+    let len = env.get_array_length(array)?;
+    let mut result = Vec::with_capacity(len as usize);
+    for i in 0..len {
+        let element = env.get_object_array_element(array, i)?;
+        let element = env.auto_local(element);
+        if element.is_null() {
+            return Err(anyhow!("object array element {} is null", i));
+        }
+        result.push(env.new_global_ref(&element)?);
+    }
+    Ok(result)
+}
+
+/// copy a java `boolean[]` into a `Vec<bool>`, any nonzero element is `true`
+pub fn jboolean_array_to_vec(env: &JNIEnv, array: &JBooleanArray) -> Result<Vec<bool>> {
+    let len = env.get_array_length(array)? as usize;
+    let mut raw = vec![0; len];
+    env.get_boolean_array_region(array, 0, &mut raw)?;
+    Ok(raw.into_iter().map(jboolean_to_bool).collect())
+}
+
+/// a new java `boolean[]` holding `values`
+pub fn vec_to_jboolean_array<'local>(
+    env: &JNIEnv<'local>,
+    values: &[bool],
+) -> Result<JBooleanArray<'local>> {
+    let raw: Vec<jboolean> = values.iter().copied().map(bool_to_jboolean).collect();
+    let array = env.new_boolean_array(raw.len() as jsize)?;
+    env.set_boolean_array_region(&array, 0, &raw)?;
+    Ok(array)
+}
+
+/// a new java `long[]` holding `values`, to pass as a `call` argument.
+/// the local ref is deleted when the returned value is dropped
+pub fn long_array_arg<'local>(
+    env: &JNIEnv<'local>,
+    values: &[i64],
+) -> Result<AutoLocal<'local, JLongArray<'local>>> {
+    let array = env.new_long_array(values.len() as jsize)?;
+    env.set_long_array_region(&array, 0, values)?;
+    Ok(env.auto_local(array))
+}
+
+/// a new java `double[]` holding `values`, to pass as a `call` argument.
+/// the local ref is deleted when the returned value is dropped
+pub fn double_array_arg<'local>(
+    env: &JNIEnv<'local>,
+    values: &[f64],
+) -> Result<AutoLocal<'local, JDoubleArray<'local>>> {
+    let array = env.new_double_array(values.len() as jsize)?;
+    env.set_double_array_region(&array, 0, values)?;
+    Ok(env.auto_local(array))
+}
+
+/// whether the writes to an `ArrayRegionGuard` or a `DirectBufferGuard` go back to java when
+/// it is dropped
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum WriteBack {
+    Commit,
+    Abort,
+}
+
+/// the elements of a java primitive array, released back to the jvm when dropped
+pub struct ArrayRegionGuard<'local, 'other_local, 'array, T: TypeArray> {
+    elements: AutoElements<'local, 'other_local, 'array, T>,
+}
+
+impl<T: TypeArray> ArrayRegionGuard<'_, '_, '_, T> {
+    /// copy the writes so far back to the java array, the guard stays usable
+    pub fn commit(&mut self) -> Result<()> {
+        self.elements.commit()?;
+        Ok(())
+    }
+
+    /// release without copying back, whatever the `WriteBack` mode was
+    pub fn abort(mut self) {
+        self.elements.discard();
+    }
+}
+
+impl<T: TypeArray> Deref for ArrayRegionGuard<'_, '_, '_, T> {
+    type Target = [T];
+
+    fn deref(&self) -> &[T] {
+        &self.elements
+    }
+}
+
+impl<T: TypeArray> DerefMut for ArrayRegionGuard<'_, '_, '_, T> {
+    fn deref_mut(&mut self) -> &mut [T] {
+        &mut self.elements
+    }
+}
+
+/// # Safety
+/// same as `JNIEnv::get_array_elements`: only one guard per java array at a time,
+/// and no java thread may write the array while the guard is alive
+pub unsafe fn pin_array<'local, 'other_local, 'array, T: TypeArray>(
+    env: &mut JNIEnv<'local>,
+    array: &'array JPrimitiveArray<'other_local, T>,
+    mode: WriteBack,
+) -> Result<ArrayRegionGuard<'local, 'other_local, 'array, T>> {
+    let mode = match mode {
+        WriteBack::Commit => ReleaseMode::CopyBack,
+        WriteBack::Abort => ReleaseMode::NoCopyBack,
+    };
+    let elements = unsafe { env.get_array_elements(array, mode)? };
+    Ok(ArrayRegionGuard { elements })
+}
+
+/// the bytes of a java direct `ByteBuffer` (`ByteBuffer.allocateDirect`), copied out like
+/// `pin_array` may copy an array: java only sees the writes once they are committed, on drop
+/// with `WriteBack::Commit` or by `commit`
+pub struct DirectBufferGuard<'buffer> {
+    address: *mut u8,
+    bytes: Vec<u8>,
+    mode: WriteBack,
+    buffer: PhantomData<&'buffer JByteBuffer<'buffer>>,
+}
+
+impl DirectBufferGuard<'_> {
+    /// copy the writes so far back to the buffer, the guard stays usable
+    pub fn commit(&mut self) {
+        unsafe {
+            std::ptr::copy_nonoverlapping(self.bytes.as_ptr(), self.address, self.bytes.len())
+        };
+    }
+
+    /// release without copying back, whatever the `WriteBack` mode was
+    pub fn abort(mut self) {
+        self.mode = WriteBack::Abort;
+    }
+}
+
+impl Drop for DirectBufferGuard<'_> {
+    fn drop(&mut self) {
+        if self.mode == WriteBack::Commit {
+            self.commit();
+        }
+    }
+}
+
+impl Deref for DirectBufferGuard<'_> {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        &self.bytes
+    }
+}
+
+impl DerefMut for DirectBufferGuard<'_> {
+    fn deref_mut(&mut self) -> &mut [u8] {
+        &mut self.bytes
+    }
+}
+
+/// # Safety
+/// the memory of `buffer` must stay allocated while the guard is alive (the buffer is borrowed,
+/// but a buffer over native memory can be freed by its owner), and no java thread may write
+/// it meanwhile, or the commit overwrites those writes
+pub unsafe fn pin_direct_buffer<'buffer>(
+    env: &JNIEnv,
+    buffer: &'buffer JByteBuffer,
+    mode: WriteBack,
+) -> Result<DirectBufferGuard<'buffer>> {
+    let address = env.get_direct_buffer_address(buffer)?;
+    let capacity = env.get_direct_buffer_capacity(buffer)?;
+    let bytes = unsafe { std::slice::from_raw_parts(address, capacity) }.to_vec();
+    Ok(DirectBufferGuard {
+        address,
+        bytes,
+        mode,
+        buffer: PhantomData,
+    })
+}
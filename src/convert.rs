@@ -0,0 +1,160 @@
+use jni::{
+    JNIEnv,
+    objects::{JObject, JString, JValue, JValueGen, JValueOwned},
+    sys::{JNI_FALSE, JNI_TRUE, jboolean, jchar, jvalue},
+};
+
+use crate::error::{Result, anyhow};
+
+/// any nonzero `jboolean` is `true`, as the jni spec says
+#[inline]
+pub fn jboolean_to_bool(b: jboolean) -> bool {
+    b != JNI_FALSE
+}
+
+#[inline]
+pub fn bool_to_jboolean(b: bool) -> jboolean {
+    if b { JNI_TRUE } else { JNI_FALSE }
+}
+
+/// a java `char` (one utf-16 unit) as a rust `char`, a lone surrogate is an error
+#[inline]
+pub fn java_char_to_rust(c: jchar) -> Result<char> {
+    char::from_u32(c as u32).ok_or_else(|| anyhow!("invalid char, lone surrogate: {:#x}", c))
+}
+
+/// a returned `String` object as a rust `String`, `null` is `None`. the local ref is deleted
+pub fn jstring_to_string(env: &mut JNIEnv, value: JObject) -> Result<Option<String>> {
+    if value.is_null() {
+        return Ok(None);
+    }
+    let value = JString::from(value);
+    let result = env.get_string(&value)?.into();
+    env.delete_local_ref(value)?;
+    Ok(Some(result))
+}
+
+/// the raw `args` of `call`: `jvalues(&[JValue::Int(1), JValue::Object(&obj)])`
+pub fn jvalues(values: &[JValue]) -> Vec<jvalue> {
+    values.iter().map(|v| v.as_jni()).collect()
+}
+
+/// same as `jvalues` for owned values, the local refs stay owned by `values`
+/// and must outlive the call that takes the result
+pub fn owned_jvalues(values: &[JValueOwned]) -> Vec<jvalue> {
+    values.iter().map(|v| v.borrow().as_jni()).collect()
+}
+
+/// same as `jvalues` without the allocation
+pub fn jvalue_array<const N: usize>(values: [JValue; N]) -> [jvalue; N] {
+    values.map(|v| v.as_jni())
+}
+
+/// a java `null` argument for `call`
+#[inline]
+pub fn null_arg() -> jvalue {
+    jvalue {
+        l: std::ptr::null_mut(),
+    }
+}
+
+/// an object argument for `call`, `None` is `null`
+#[inline]
+pub fn object_arg(obj: Option<&JObject>) -> jvalue {
+    match obj {
+        Some(obj) => JValue::Object(obj).as_jni(),
+        None => null_arg(),
+    }
+}
+
+/// an argument of `call_with`: a `&str` becomes a java `String` for the duration of the call
+#[derive(Clone, Copy, Debug)]
+pub enum SpArg<'a> {
+    Value(JValue<'a, 'a>),
+    Str(&'a str),
+}
+
+impl<'a> From<JValue<'a, 'a>> for SpArg<'a> {
+    fn from(value: JValue<'a, 'a>) -> Self {
+        Self::Value(value)
+    }
+}
+
+impl<'a> From<&'a str> for SpArg<'a> {
+    fn from(value: &'a str) -> Self {
+        Self::Str(value)
+    }
+}
+
+/// run `call` with the raw form of `args`, the strings made for `SpArg::Str` (converted to
+/// modified utf-8 by `new_string`) are deleted afterwards, also when `call` fails
+pub(crate) fn with_sp_args<'local, R>(
+    env: &mut JNIEnv<'local>,
+    args: &[SpArg],
+    call: impl FnOnce(&mut JNIEnv<'local>, &[jvalue]) -> Result<R>,
+) -> Result<R> {
+    let mut strings = Vec::new();
+    let mut raw = Vec::with_capacity(args.len());
+    for arg in args {
+        match arg {
+            SpArg::Value(value) => raw.push(value.as_jni()),
+            SpArg::Str(s) => {
+                let string = env.auto_local(env.new_string(s)?);
+                raw.push(JValue::Object(&string).as_jni());
+                strings.push(string);
+            }
+        }
+    }
+    call(env, &raw)
+}
+
+/// fallible extraction of the value returned by `call`, a wrong variant is an error instead of a panic
+pub trait FromJValue: Sized {
+    fn from_jvalue(value: JValueGen<JObject>) -> Result<Self>;
+}
+
+macro_rules! impl_from_jvalue {
+    ($t:ty, $variant:ident, $name:expr, $v:ident => $convert:expr) => {
+        impl FromJValue for $t {
+            fn from_jvalue(value: JValueGen<JObject>) -> Result<Self> {
+                match value {
+                    JValueGen::$variant($v) => $convert,
+                    other => Err(anyhow!(
+                        "jvalue type error: expect {}, got {}",
+                        $name,
+                        other.type_name()
+                    )),
+                }
+            }
+        }
+    };
+}
+
+impl_from_jvalue!(i32, Int, "int", v => Ok(v));
+impl_from_jvalue!(i64, Long, "long", v => Ok(v));
+impl_from_jvalue!(bool, Bool, "boolean", v => Ok(jboolean_to_bool(v)));
+impl_from_jvalue!(f32, Float, "float", v => Ok(v));
+impl_from_jvalue!(f64, Double, "double", v => Ok(v));
+impl_from_jvalue!(u8, Byte, "byte", v => Ok(v as u8));
+impl_from_jvalue!(i8, Byte, "byte", v => Ok(v));
+impl_from_jvalue!(i16, Short, "short", v => Ok(v));
+impl_from_jvalue!(char, Char, "char", v => java_char_to_rust(v));
+
+/// convert the object of a `JValueGen`, primitives are copied
+pub(crate) fn map_jvalue_object<A, B>(
+    value: &JValueGen<A>,
+    map: impl FnOnce(&A) -> Result<B>,
+) -> Result<JValueGen<B>> {
+    Ok(match value {
+        JValueGen::Object(obj) => JValueGen::Object(map(obj)?),
+        JValueGen::Byte(v) => JValueGen::Byte(*v),
+        JValueGen::Char(v) => JValueGen::Char(*v),
+        JValueGen::Short(v) => JValueGen::Short(*v),
+        JValueGen::Int(v) => JValueGen::Int(*v),
+        JValueGen::Long(v) => JValueGen::Long(*v),
+        JValueGen::Bool(v) => JValueGen::Bool(*v),
+        JValueGen::Float(v) => JValueGen::Float(*v),
+        JValueGen::Double(v) => JValueGen::Double(*v),
+        JValueGen::Void => JValueGen::Void,
+    })
+}
@@ -0,0 +1,85 @@
+use mini_moka::sync::Cache;
+use std::fmt::Display;
+
+use crate::jni::{
+    CLASS_CACHE, ClassKey, FIELD_CACHE, FieldKey, METHOD_CACHE, MethodEntry, MethodKey,
+    STATIC_FIELD_CACHE, STATIC_FIELD_VALUE_CACHE, STATIC_METHOD_CACHE, StaticFieldKey,
+    StaticMethodKey,
+};
+
+/// the keys in every cache at the time of `dump_caches`, sorted
+#[derive(Clone, Debug, Default)]
+pub struct CacheDump {
+    pub classes: Vec<ClassKey>,
+    pub fields: Vec<FieldKey>,
+    /// methods and static methods also keep their parameter count
+    pub methods: Vec<(MethodKey, usize)>,
+    pub static_fields: Vec<StaticFieldKey>,
+    pub static_field_values: Vec<StaticFieldKey>,
+    pub static_methods: Vec<(StaticMethodKey, usize)>,
+}
+
+fn keys<V>(cache: &Cache<i32, V>) -> Vec<i32>
+where
+    V: Clone + Send + Sync + 'static,
+{
+    let mut keys: Vec<i32> = cache.iter().map(|entry| *entry.key()).collect();
+    keys.sort_unstable();
+    keys
+}
+
+fn keys_with_arity(cache: &Cache<i32, MethodEntry>) -> Vec<(i32, usize)> {
+    let mut keys: Vec<(i32, usize)> = cache
+        .iter()
+        .map(|entry| (*entry.key(), entry.value().arity))
+        .collect();
+    keys.sort_unstable();
+    keys
+}
+
+/// snapshot of the cache state, for bug reports
+pub fn dump_caches() -> CacheDump {
+    CacheDump {
+        classes: keys(&CLASS_CACHE),
+        fields: keys(&FIELD_CACHE),
+        methods: keys_with_arity(&METHOD_CACHE),
+        static_fields: keys(&STATIC_FIELD_CACHE),
+        static_field_values: keys(&STATIC_FIELD_VALUE_CACHE),
+        static_methods: keys_with_arity(&STATIC_METHOD_CACHE),
+    }
+}
+
+fn write_keys<K: Display>(
+    f: &mut std::fmt::Formatter<'_>,
+    name: &str,
+    keys: impl ExactSizeIterator<Item = K>,
+) -> std::fmt::Result {
+    write!(f, "{} ({}):", name, keys.len())?;
+    for key in keys {
+        write!(f, " {}", key)?;
+    }
+    writeln!(f)
+}
+
+impl Display for CacheDump {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write_keys(f, "classes", self.classes.iter())?;
+        write_keys(f, "fields", self.fields.iter())?;
+        write_keys(
+            f,
+            "methods (key/arity)",
+            self.methods
+                .iter()
+                .map(|(key, arity)| format!("{}/{}", key, arity)),
+        )?;
+        write_keys(f, "static fields", self.static_fields.iter())?;
+        write_keys(f, "static field values", self.static_field_values.iter())?;
+        write_keys(
+            f,
+            "static methods (key/arity)",
+            self.static_methods
+                .iter()
+                .map(|(key, arity)| format!("{}/{}", key, arity)),
+        )
+    }
+}
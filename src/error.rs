@@ -1,6 +1,168 @@
+use jni::{
+    JNIEnv,
+    objects::{JThrowable, JValue},
+};
+use std::fmt::Display;
+
 pub use anyhow::{Result, anyhow};
 
+use crate::jni::ClassKey;
+
 #[inline]
 pub fn throw<T>(info: &str) -> Result<T> {
     Err(anyhow!("{}", info))
 }
+
+/// `throw` with a format string: `return throw_fmt!("bad key: {}", key)`.
+/// the message is only formatted in the error branch, unlike a `format!` made up front for
+/// `throw`, so a check that passes allocates nothing (see `tests/throw_fmt.rs`)
+#[macro_export]
+macro_rules! throw_fmt {
+    ($($arg:tt)*) => {
+        Err($crate::anyhow!($($arg)*))
+    };
+}
+
+/// throw a new `class` (like `java/lang/IllegalStateException`) with `msg`,
+/// the pending exception, if there is one, becomes its cause instead of being lost
+pub fn throw_with_cause(env: &mut JNIEnv, class: &str, msg: &str) -> Result<()> {
+    let cause = if env.exception_check()? {
+        let cause = env.exception_occurred()?;
+        env.exception_clear()?;
+        Some(cause)
+    } else {
+        None
+    };
+    match new_with_cause(env, class, msg, cause.as_ref()) {
+        Ok(exception) => env.throw(exception)?,
+        Err(e) => {
+            // the wrapper could not be built, put the cleared cause back instead of losing it
+            if let Some(cause) = cause {
+                env.exception_clear()?;
+                env.throw(cause)?;
+            }
+            return Err(e);
+        }
+    }
+    Ok(())
+}
+
+fn new_with_cause<'local>(
+    env: &mut JNIEnv<'local>,
+    class: &str,
+    msg: &str,
+    cause: Option<&JThrowable>,
+) -> Result<JThrowable<'local>> {
+    let msg = env.new_string(msg)?;
+    let exception = env.new_object(class, "(Ljava/lang/String;)V", &[JValue::Object(&msg)])?;
+    if let Some(cause) = cause {
+        env.call_method(
+            &exception,
+            "initCause",
+            "(Ljava/lang/Throwable;)Ljava/lang/Throwable;",
+            &[JValue::Object(cause)],
+        )?;
+    }
+    Ok(JThrowable::from(exception))
+}
+
+/// run `action`, and if it fails with a pending `OutOfMemoryError` clear it, run `cleanup`
+/// (pop a local frame, drop caches, `System.gc()` ...) and run `action` once more.
+/// any other failure, and a second failure, is returned as is
+pub fn retry_on_oom<'local, T>(
+    env: &mut JNIEnv<'local>,
+    mut action: impl FnMut(&mut JNIEnv<'local>) -> Result<T>,
+    cleanup: impl FnOnce(&mut JNIEnv<'local>),
+) -> Result<T> {
+    let error = match action(env) {
+        Ok(value) => return Ok(value),
+        Err(e) => e,
+    };
+    if !env.exception_check()? {
+        return Err(error);
+    }
+    // the class lookup below can not run with a pending exception, put it back if it is not oom
+    let exception = env.exception_occurred()?;
+    env.exception_clear()?;
+    if !env.is_instance_of(&exception, "java/lang/OutOfMemoryError")? {
+        env.throw(exception)?;
+        return Err(error);
+    }
+    env.delete_local_ref(exception)?;
+    cleanup(env);
+    action(env)
+}
+
+/// errors a caller may want to tell apart, find them with `error.downcast_ref::<SpError>()`
+#[derive(Debug)]
+pub enum SpError {
+    /// the class of the key was evicted from `CLASS_CACHE`, and the handle was made by
+    /// `SpClass::cache` so it has no class name to find it again. build it with `SpClass::new`
+    CacheEvicted(ClassKey),
+    /// no `JavaVM` was stored, or this thread could not be attached to it (e.g. the jvm is
+    /// shutting down). an error of the action itself is never this variant
+    AttachFailed(String),
+}
+
+impl Display for SpError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::CacheEvicted(key) => write!(f, "class cache evicted: key={}", key),
+            Self::AttachFailed(reason) => write!(f, "attach to java vm failed: {}", reason),
+        }
+    }
+}
+
+impl std::error::Error for SpError {}
+
+/// why `try_jni` failed
+#[derive(Debug)]
+pub enum JniBoundaryError {
+    /// the closure panicked, with the panic message
+    Panic(String),
+    /// the closure returned an error, a java exception it raised is still pending
+    Error(anyhow::Error),
+}
+
+impl Display for JniBoundaryError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Panic(msg) => write!(f, "panic in jni code: {}", msg),
+            Self::Error(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for JniBoundaryError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Panic(_) => None,
+            Self::Error(e) => Some(e.as_ref()),
+        }
+    }
+}
+
+/// run `action` and hand both its error and a panic back to the rust caller, nothing is thrown
+/// to java. for native code calling the logic of another native function.
+///
+/// a panic is only caught with `panic = "unwind"`: built with `panic = "abort"`
+/// (`cfg(panic = "abort")`), a panic in `action` aborts the process, jvm included,
+/// and `JniBoundaryError::Panic` is never returned
+pub fn try_jni<'local, R>(
+    env: &mut JNIEnv<'local>,
+    action: impl FnOnce(&mut JNIEnv<'local>) -> Result<R>,
+) -> std::result::Result<R, JniBoundaryError> {
+    use std::panic::{AssertUnwindSafe, catch_unwind};
+    match catch_unwind(AssertUnwindSafe(|| action(env))) {
+        Ok(Ok(value)) => Ok(value),
+        Ok(Err(e)) => Err(JniBoundaryError::Error(e)),
+        Err(payload) => {
+            let msg = payload
+                .downcast_ref::<&str>()
+                .map(|msg| msg.to_string())
+                .or_else(|| payload.downcast_ref::<String>().cloned())
+                .unwrap_or_else(|| "unknown panic".to_string());
+            Err(JniBoundaryError::Panic(msg))
+        }
+    }
+}
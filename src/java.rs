@@ -0,0 +1,289 @@
+use jni::{
+    JNIEnv,
+    objects::{GlobalRef, JObject, JValue},
+    signature::{Primitive, ReturnType},
+};
+use std::sync::{LazyLock, Mutex, MutexGuard, PoisonError};
+
+use crate::error::Result;
+use crate::get_sp_struct;
+use crate::jni::{
+    ClassKey, MethodKey, SpClass, SpMethod, SpStaticMethod, SpType, StaticMethodKey,
+    construct_and_set,
+};
+use crate::point::{Point, free_ptr};
+
+/// Keys from `RESERVED_KEY_START` up to `i32::MAX` are used by the helpers of this crate,
+/// user code should pick its cache keys below it.
+pub const RESERVED_KEY_START: i32 = i32::MAX - 1024;
+
+const OPTIONAL_CLASS: ClassKey = i32::MAX;
+const OPTIONAL_IS_PRESENT: MethodKey = i32::MAX;
+const OPTIONAL_GET: MethodKey = i32::MAX - 1;
+const OPTIONAL_OF_NULLABLE: StaticMethodKey = i32::MAX;
+const BI_CONSUMER_CLASS: ClassKey = i32::MAX - 1;
+const BI_CONSUMER_ACCEPT: MethodKey = i32::MAX - 2;
+const OBJECT_CLASS: ClassKey = i32::MAX - 2;
+const OBJECT_TO_STRING: MethodKey = i32::MAX - 3;
+/// keys of `int.class` and the other primitive classes, one for each primitive from here down
+pub(crate) const PRIMITIVE_CLASS_START: ClassKey = i32::MAX - 3;
+const REFERENCE_QUEUE_CLASS: ClassKey = i32::MAX - 12;
+const REFERENCE_QUEUE_NEW: MethodKey = i32::MAX - 4;
+const REFERENCE_QUEUE_POLL: MethodKey = i32::MAX - 5;
+const PHANTOM_REFERENCE_CLASS: ClassKey = i32::MAX - 13;
+const PHANTOM_REFERENCE_NEW: MethodKey = i32::MAX - 6;
+/// how many keys of each cache the helpers above take, the caches hold them on top of the
+/// capacities of `CacheConfig`. class keys run from `i32::MAX` down to `PHANTOM_REFERENCE_CLASS`
+/// (the primitive classes included), method keys down to `PHANTOM_REFERENCE_NEW`
+pub(crate) const RESERVED_CLASS_KEYS: u64 = (i32::MAX - PHANTOM_REFERENCE_CLASS) as u64 + 1;
+pub(crate) const RESERVED_METHOD_KEYS: u64 = (i32::MAX - PHANTOM_REFERENCE_NEW) as u64 + 1;
+pub(crate) const RESERVED_STATIC_METHOD_KEYS: u64 = 1;
+
+fn optional_class(env: &mut JNIEnv) -> Result<SpClass> {
+    let mut class = get_sp_struct!(class: env, OPTIONAL_CLASS, "java.util.Optional")?;
+    class.init(env)?;
+    Ok(class)
+}
+
+/// `Optional<T>` -> `Option<JObject>`, a null `Optional` is read as `None`
+pub fn optional_to_option<'local>(
+    env: &mut JNIEnv<'local>,
+    opt: &JObject,
+) -> Result<Option<JObject<'local>>> {
+    if opt.is_null() {
+        return Ok(None);
+    }
+    let class = optional_class(env)?;
+    let jclass = class.get_jni_class()?;
+    let is_present = get_sp_struct!(
+        method: env,
+        OPTIONAL_IS_PRESENT,
+        jclass,
+        "isPresent",
+        &SpType::Boolean,
+        &[]
+    )?;
+    let get = get_sp_struct!(
+        method: env,
+        OPTIONAL_GET,
+        jclass,
+        "get",
+        &SpType::new_class("java.lang.Object"),
+        &[]
+    )?;
+
+    if !is_present.call_bool(env, opt, &[])? {
+        return Ok(None);
+    }
+    let value = get.call(env, opt, &[], ReturnType::Object)?.l()?;
+    Ok(Some(value))
+}
+
+/// `Option<&JObject>` -> `Optional<T>`, both `None` and a null object become `Optional.empty()`
+pub fn option_to_optional<'local>(
+    env: &mut JNIEnv<'local>,
+    value: Option<&JObject>,
+) -> Result<JObject<'local>> {
+    let class = optional_class(env)?;
+    let jclass = class.get_jni_class()?;
+    let of_nullable = get_sp_struct!(
+        static_method: env,
+        OPTIONAL_OF_NULLABLE,
+        jclass,
+        "ofNullable",
+        &SpType::new_class("java.util.Optional"),
+        &[SpType::new_class("java.lang.Object")]
+    )?;
+
+    let null = JObject::null();
+    let arg = JValue::Object(value.unwrap_or(&null)).as_jni();
+    let result = of_nullable
+        .call(env, jclass, &[arg], ReturnType::Object)?
+        .l()?;
+    Ok(result)
+}
+
+/// `BiConsumer.accept(first, second)`
+pub fn bi_consumer_accept(
+    env: &mut JNIEnv,
+    consumer: &JObject,
+    first: &JObject,
+    second: &JObject,
+) -> Result<()> {
+    let mut class = get_sp_struct!(class: env, BI_CONSUMER_CLASS, "java.util.function.BiConsumer")?;
+    class.init(env)?;
+    let jclass = class.get_jni_class()?;
+    let accept = get_sp_struct!(
+        method: env,
+        BI_CONSUMER_ACCEPT,
+        jclass,
+        "accept",
+        &SpType::Void,
+        &[
+            SpType::new_class("java.lang.Object"),
+            SpType::new_class("java.lang.Object")
+        ]
+    )?;
+
+    let args = [
+        JValue::Object(first).as_jni(),
+        JValue::Object(second).as_jni(),
+    ];
+    accept.call(env, consumer, &args, ReturnType::Primitive(Primitive::Void))?;
+    Ok(())
+}
+
+/// `obj.toString()` as a rust `String`, a null object is `"null"`
+pub fn debug_to_string(env: &mut JNIEnv, obj: &JObject) -> Result<String> {
+    if obj.is_null() {
+        return Ok("null".to_string());
+    }
+    let mut class = get_sp_struct!(class: env, OBJECT_CLASS, "java.lang.Object")?;
+    class.init(env)?;
+    let jclass = class.get_jni_class()?;
+    let to_string = get_sp_struct!(
+        method: env,
+        OBJECT_TO_STRING,
+        jclass,
+        "toString",
+        &SpType::new_class("java.lang.String"),
+        &[]
+    )?;
+
+    let value = to_string.call_string(env, obj, &[])?;
+    Ok(value.unwrap_or_else(|| "null".to_string()))
+}
+
+/// a `register_cleaner` call, `free` is `free_ptr` of the type `ptr` was made with
+struct Cleaner {
+    reference: GlobalRef,
+    ptr: Point,
+    free: fn(Point) -> Result<()>,
+}
+
+#[derive(Default)]
+struct Cleaners {
+    queue: Option<GlobalRef>,
+    pending: Vec<Cleaner>,
+}
+
+/// the one `ReferenceQueue` of every `register_cleaner` reference, and the references in flight.
+/// a `PhantomReference` is only queued while it is reachable, `pending` keeps it so
+static CLEANERS: LazyLock<Mutex<Cleaners>> = LazyLock::new(Default::default);
+
+fn cleaners() -> MutexGuard<'static, Cleaners> {
+    CLEANERS.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+fn new_reference_queue(env: &mut JNIEnv) -> Result<GlobalRef> {
+    let mut class = get_sp_struct!(
+        class: env,
+        REFERENCE_QUEUE_CLASS,
+        "java.lang.ref.ReferenceQueue"
+    )?;
+    class.init(env)?;
+    let jclass = class.get_jni_class()?;
+    let ctor = get_sp_struct!(
+        method: env,
+        REFERENCE_QUEUE_NEW,
+        jclass,
+        "<init>",
+        &SpType::Void,
+        &[]
+    )?;
+    let queue = construct_and_set(env, &class, &ctor, &[], &[])?;
+    let queue = env.auto_local(queue);
+    Ok(env.new_global_ref(&queue)?)
+}
+
+/// free the `to_ptr::<T>` pointer `ptr` after `owner` is garbage collected, for bindings
+/// without a java `close()`. the gc only queues a `PhantomReference` of `owner`, the value is
+/// dropped by the next `run_cleaners` after that. when (and whether) `owner` is collected is
+/// up to the gc, so do not count on it for anything but memory
+pub fn register_cleaner<T: 'static>(env: &mut JNIEnv, owner: &JObject, ptr: Point) -> Result<()> {
+    let mut cleaners = cleaners();
+    let queue = match &cleaners.queue {
+        Some(queue) => queue.clone(),
+        None => {
+            let queue = new_reference_queue(env)?;
+            cleaners.queue = Some(queue.clone());
+            queue
+        }
+    };
+    let mut class = get_sp_struct!(
+        class: env,
+        PHANTOM_REFERENCE_CLASS,
+        "java.lang.ref.PhantomReference"
+    )?;
+    class.init(env)?;
+    let jclass = class.get_jni_class()?;
+    let ctor = get_sp_struct!(
+        method: env,
+        PHANTOM_REFERENCE_NEW,
+        jclass,
+        "<init>",
+        &SpType::Void,
+        &[
+            SpType::new_class("java.lang.Object"),
+            SpType::new_class("java.lang.ref.ReferenceQueue")
+        ]
+    )?;
+    let args = [
+        JValue::Object(owner).as_jni(),
+        JValue::Object(queue.as_obj()).as_jni(),
+    ];
+    let reference = construct_and_set(env, &class, &ctor, &args, &[])?;
+    let reference = env.auto_local(reference);
+    cleaners.pending.push(Cleaner {
+        reference: env.new_global_ref(&reference)?,
+        ptr,
+        free: free_ptr::<T>,
+    });
+    Ok(())
+}
+
+/// the cleanup entry point of `register_cleaner`: free the values of every owner collected
+/// so far and return how many. call it from a native method (a java `static native int
+/// runCleaners()`) or a thread of your own, nothing is freed until it runs
+pub fn run_cleaners(env: &mut JNIEnv) -> Result<usize> {
+    let mut cleaners = cleaners();
+    let Some(queue) = cleaners.queue.clone() else {
+        return Ok(0);
+    };
+    let mut class = get_sp_struct!(
+        class: env,
+        REFERENCE_QUEUE_CLASS,
+        "java.lang.ref.ReferenceQueue"
+    )?;
+    class.init(env)?;
+    let jclass = class.get_jni_class()?;
+    let poll = get_sp_struct!(
+        method: env,
+        REFERENCE_QUEUE_POLL,
+        jclass,
+        "poll",
+        &SpType::new_class("java.lang.ref.Reference"),
+        &[]
+    )?;
+
+    let mut freed = 0;
+    loop {
+        let reference = poll.call(env, &queue, &[], ReturnType::Object)?.l()?;
+        if reference.is_null() {
+            return Ok(freed);
+        }
+        let reference = env.auto_local(reference);
+        let mut found = None;
+        for (i, cleaner) in cleaners.pending.iter().enumerate() {
+            if env.is_same_object(&cleaner.reference, &reference)? {
+                found = Some(i);
+                break;
+            }
+        }
+        if let Some(i) = found {
+            let cleaner = cleaners.pending.swap_remove(i);
+            (cleaner.free)(cleaner.ptr)?;
+            freed += 1;
+        }
+    }
+}
@@ -1,16 +1,219 @@
+use anyhow::Context;
 use jni::{
     JNIEnv,
     objects::{
-        GlobalRef, JClass, JFieldID, JMethodID, JObject, JStaticFieldID, JStaticMethodID, JValueGen,
+        AutoLocal, GlobalRef, JByteArray, JClass, JFieldID, JMethodID, JObject, JObjectArray,
+        JStaticFieldID, JStaticMethodID, JString, JValue, JValueGen, JValueOwned, WeakRef,
     },
-    signature::{JavaType, ReturnType},
-    sys::{jfieldID, jmethodID, jvalue},
+    signature::{JavaType, Primitive, ReturnType},
+    sys::{JNI_FALSE, JNI_TRUE, jfieldID, jmethodID, jvalue},
 };
 use mini_moka::sync::Cache;
+use std::collections::{HashMap, HashSet};
 use std::fmt::Display;
-use std::sync::LazyLock;
+use std::hash::{Hash, Hasher};
+use std::sync::{Arc, Condvar, LazyLock, Mutex, OnceLock, PoisonError};
+use std::thread::{self, ThreadId};
 
-use crate::{error::Result, throw};
+use crate::{
+    array::{NullPolicy, jbyte_array_to_vec, object_array_to_globals},
+    convert::{
+        FromJValue, SpArg, jstring_to_string, map_jvalue_object, object_arg, owned_jvalues,
+        with_sp_args,
+    },
+    error::{Result, SpError, anyhow},
+    java::{
+        PRIMITIVE_CLASS_START, RESERVED_CLASS_KEYS, RESERVED_METHOD_KEYS,
+        RESERVED_STATIC_METHOD_KEYS,
+    },
+    throw,
+};
+
+/// `(args)ret` method descriptor, a `Void` argument is a bug in the binding and panics
+pub fn build_signature(return_type: &SpType, args: &[SpType]) -> String {
+    let mut all_len = return_type.get_str_len() + 2;
+    for n in args {
+        assert!(
+            *n != SpType::Void,
+            "void can not be a method parameter type"
+        );
+        all_len += n.get_str_len();
+    }
+    let mut sig_builder = String::with_capacity(all_len);
+    sig_builder.push('(');
+    for n in args {
+        n.write_descriptor(&mut sig_builder);
+    }
+    sig_builder.push(')');
+    return_type.write_descriptor(&mut sig_builder);
+    sig_builder
+}
+
+/// the typed `call_*` helpers of `SpMethod` (`this: &JObject`) and `SpStaticMethod`
+/// (`class: &JClass`), one definition so both get the same conversions
+macro_rules! typed_calls {
+    ($recv:ident: $recv_ty:ty) => {
+        /// same as `call` with `SpArg` arguments: `call_with(env, x, &["key".into()], ret)`
+        pub fn call_with<'local>(
+            &self,
+            env: &mut JNIEnv<'local>,
+            $recv: $recv_ty,
+            args: &[SpArg],
+            ret: ReturnType,
+        ) -> Result<JValueGen<JObject<'local>>> {
+            with_sp_args(env, args, |env, args| self.call(env, $recv, args, ret))
+        }
+
+        /// same as `call` with owned arguments (`JValueOwned`), they are only borrowed for the call
+        pub fn call_owned<'local>(
+            &self,
+            env: &mut JNIEnv<'local>,
+            $recv: $recv_ty,
+            args: &[JValueOwned],
+            ret: ReturnType,
+        ) -> Result<JValueGen<JObject<'local>>> {
+            self.call(env, $recv, &owned_jvalues(args), ret)
+        }
+
+        /// call a `void` method
+        pub fn call_void(&self, env: &mut JNIEnv, $recv: $recv_ty, args: &[jvalue]) -> Result<()> {
+            self.call(env, $recv, args, SpType::Void.to_return_type())?
+                .v()?;
+            Ok(())
+        }
+
+        pub fn call_bool(
+            &self,
+            env: &mut JNIEnv,
+            $recv: $recv_ty,
+            args: &[jvalue],
+        ) -> Result<bool> {
+            let value = self.call(env, $recv, args, ReturnType::Primitive(Primitive::Boolean))?;
+            bool::from_jvalue(value)
+        }
+
+        pub fn call_char(
+            &self,
+            env: &mut JNIEnv,
+            $recv: $recv_ty,
+            args: &[jvalue],
+        ) -> Result<char> {
+            let value = self.call(env, $recv, args, ReturnType::Primitive(Primitive::Char))?;
+            char::from_jvalue(value)
+        }
+
+        pub fn call_int(&self, env: &mut JNIEnv, $recv: $recv_ty, args: &[jvalue]) -> Result<i32> {
+            let value = self.call(env, $recv, args, ReturnType::Primitive(Primitive::Int))?;
+            i32::from_jvalue(value)
+        }
+
+        pub fn call_short(
+            &self,
+            env: &mut JNIEnv,
+            $recv: $recv_ty,
+            args: &[jvalue],
+        ) -> Result<i16> {
+            let value = self.call(env, $recv, args, ReturnType::Primitive(Primitive::Short))?;
+            i16::from_jvalue(value)
+        }
+
+        pub fn call_byte(&self, env: &mut JNIEnv, $recv: $recv_ty, args: &[jvalue]) -> Result<i8> {
+            let value = self.call(env, $recv, args, ReturnType::Primitive(Primitive::Byte))?;
+            i8::from_jvalue(value)
+        }
+
+        /// call a method returning `String`, a `null` return is `None`
+        pub fn call_string(
+            &self,
+            env: &mut JNIEnv,
+            $recv: $recv_ty,
+            args: &[jvalue],
+        ) -> Result<Option<String>> {
+            let value = self.call(env, $recv, args, ReturnType::Object)?.l()?;
+            jstring_to_string(env, value)
+        }
+
+        /// call a method returning an object as the wrapper `T` (`JString`, `JObjectArray` ...).
+        /// with `expect`, a non `null` result must be an instance of that class
+        pub fn call_as<'local, T: From<JObject<'local>>>(
+            &self,
+            env: &mut JNIEnv<'local>,
+            $recv: $recv_ty,
+            args: &[jvalue],
+            expect: Option<&SpClass>,
+        ) -> Result<T> {
+            let value = self.call(env, $recv, args, ReturnType::Object)?.l()?;
+            if let Some(class) = expect
+                && !value.is_null()
+            {
+                let class_ref = class.class_ref()?;
+                if !env.is_instance_of(&value, <&JClass>::from(class_ref.as_obj()))? {
+                    return Err(anyhow!(
+                        "returned object is not a {}",
+                        class.class_full_path.as_deref().unwrap_or("cached class")
+                    ));
+                }
+            }
+            Ok(T::from(value))
+        }
+
+        /// call a method returning an object, a `null` return is `None`
+        pub fn call_object_opt<'local>(
+            &self,
+            env: &mut JNIEnv<'local>,
+            $recv: $recv_ty,
+            args: &[jvalue],
+        ) -> Result<Option<JObject<'local>>> {
+            let value = self.call(env, $recv, args, ReturnType::Object)?.l()?;
+            Ok((!value.is_null()).then_some(value))
+        }
+
+        /// call a method returning an object, the local ref is deleted when the result is dropped
+        pub fn call_auto_local<'local>(
+            &self,
+            env: &mut JNIEnv<'local>,
+            $recv: $recv_ty,
+            args: &[jvalue],
+        ) -> Result<AutoLocal<'local, JObject<'local>>> {
+            let value = self.call(env, $recv, args, ReturnType::Object)?.l()?;
+            Ok(env.auto_local(value))
+        }
+
+        /// call a method returning `Object[]` (or `String[]` ...), every element as a `GlobalRef`.
+        /// a `null` array or a `null` element is an error
+        pub fn call_global_object_array(
+            &self,
+            env: &mut JNIEnv,
+            $recv: $recv_ty,
+            args: &[jvalue],
+        ) -> Result<Vec<GlobalRef>> {
+            let value = self.call(env, $recv, args, ReturnType::Array)?.l()?;
+            let array = env.auto_local(JObjectArray::from(value));
+            object_array_to_globals(env, &array)
+        }
+
+        /// call a method returning `byte[]` and copy it, a `null` return is handled by `null`
+        pub fn call_byte_array(
+            &self,
+            env: &mut JNIEnv,
+            $recv: $recv_ty,
+            args: &[jvalue],
+            null: NullPolicy,
+        ) -> Result<Vec<u8>> {
+            let value = self.call(env, $recv, args, ReturnType::Array)?.l()?;
+            let array = env.auto_local(JByteArray::from(value));
+            jbyte_array_to_vec(env, &array, null)
+        }
+    };
+}
+
+/// trace the slow path of every handle `init`, only with the `log` feature
+macro_rules! trace_miss {
+    ($($arg:tt)*) => {
+        #[cfg(feature = "log")]
+        log::trace!($($arg)*);
+    };
+}
 
 pub type ClassKey = i32;
 pub type FieldKey = i32;
@@ -18,17 +221,163 @@ pub type StaticFieldKey = i32;
 pub type MethodKey = i32;
 pub type StaticMethodKey = i32;
 
-pub static CLASS_CACHE: LazyLock<Cache<ClassKey, GlobalRef>> = LazyLock::new(|| Cache::new(30));
-pub static FIELD_CACHE: LazyLock<Cache<FieldKey, usize>> = LazyLock::new(|| Cache::new(30));
-pub static METHOD_CACHE: LazyLock<Cache<MethodKey, usize>> = LazyLock::new(|| Cache::new(30));
+/// capacities of the caches, set once with `init_caches` before the first handle is used.
+/// every capacity gets 25% headroom on top, so registering exactly `class_capacity` classes
+/// stays clear of eviction, which loses the `GlobalRef` of a class.
+/// the keys the helpers of this crate use (from `RESERVED_KEY_START` up) are not counted,
+/// room for them is added as well
+#[derive(Clone, Copy, Debug)]
+pub struct CacheConfig {
+    pub class_capacity: u64,
+    pub field_capacity: u64,
+    pub method_capacity: u64,
+    pub static_field_capacity: u64,
+    pub static_method_capacity: u64,
+}
+
+/// 24 of each, 30 with the headroom, plus the reserved keys
+impl Default for CacheConfig {
+    fn default() -> Self {
+        Self {
+            class_capacity: 24,
+            field_capacity: 24,
+            method_capacity: 24,
+            static_field_capacity: 24,
+            static_method_capacity: 24,
+        }
+    }
+}
+
+static CACHE_CONFIG: OnceLock<CacheConfig> = OnceLock::new();
+
+/// size the caches, e.g. in `JNI_OnLoad`. it fails once any cache was used, the caches are
+/// then already built with `CacheConfig::default()`
+pub fn init_caches(config: CacheConfig) -> Result<()> {
+    CACHE_CONFIG
+        .set(config)
+        .map_err(|_| anyhow!("init_caches: the caches are already in use"))
+}
+
+/// `reserved` is the number of keys of the cache the crate itself uses
+fn new_cache<K, V>(capacity: impl FnOnce(&CacheConfig) -> u64, reserved: u64) -> Cache<K, V>
+where
+    K: Hash + Eq + Send + Sync + 'static,
+    V: Clone + Send + Sync + 'static,
+{
+    let capacity = capacity(CACHE_CONFIG.get_or_init(CacheConfig::default));
+    Cache::new(capacity + capacity.div_ceil(4) + reserved)
+}
+
+pub static CLASS_CACHE: LazyLock<Cache<ClassKey, GlobalRef>> =
+    LazyLock::new(|| new_cache(|c| c.class_capacity, RESERVED_CLASS_KEYS));
+/// keys this crate ever put in `CLASS_CACHE`, a miss on one of them means it was evicted
+static CACHED_CLASS_KEYS: LazyLock<Mutex<HashSet<ClassKey>>> =
+    LazyLock::new(|| Mutex::new(HashSet::new()));
+
+fn cache_class(key: ClassKey, class_ref: GlobalRef) {
+    CLASS_CACHE.insert(key, class_ref);
+    CACHED_CLASS_KEYS
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .insert(key);
+}
+
+fn was_class_cached(key: ClassKey) -> bool {
+    CACHED_CLASS_KEYS
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .contains(&key)
+}
+
+pub static FIELD_CACHE: LazyLock<Cache<FieldKey, FieldEntry>> =
+    LazyLock::new(|| new_cache(|c| c.field_capacity, 0));
+pub static METHOD_CACHE: LazyLock<Cache<MethodKey, MethodEntry>> =
+    LazyLock::new(|| new_cache(|c| c.method_capacity, RESERVED_METHOD_KEYS));
 pub static STATIC_FIELD_CACHE: LazyLock<Cache<StaticFieldKey, usize>> =
-    LazyLock::new(|| Cache::new(30));
-pub static STATIC_METHOD_CACHE: LazyLock<Cache<StaticMethodKey, usize>> =
-    LazyLock::new(|| Cache::new(30));
+    LazyLock::new(|| new_cache(|c| c.static_field_capacity, 0));
+pub static STATIC_FIELD_VALUE_CACHE: LazyLock<Cache<StaticFieldKey, GlobalRef>> =
+    LazyLock::new(|| new_cache(|c| c.static_field_capacity, 0));
+pub static STATIC_METHOD_CACHE: LazyLock<Cache<StaticMethodKey, MethodEntry>> =
+    LazyLock::new(|| new_cache(|c| c.static_method_capacity, RESERVED_STATIC_METHOD_KEYS));
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+enum InitKind {
+    Class,
+    StaticField,
+    Field,
+    StaticMethod,
+    Method,
+}
+
+/// the thread that is resolving a key, others wait on `released`
+#[derive(Default)]
+struct InitLock {
+    owner: Mutex<Option<ThreadId>>,
+    released: Condvar,
+}
+
+type InitLocks = HashMap<(InitKind, i32), Arc<InitLock>>;
+
+static INIT_LOCKS: LazyLock<Mutex<InitLocks>> = LazyLock::new(|| Mutex::new(HashMap::new()));
+
+struct InitGuard {
+    lock: Arc<InitLock>,
+    owned: bool,
+}
+
+impl Drop for InitGuard {
+    fn drop(&mut self) {
+        if self.owned {
+            *self
+                .lock
+                .owner
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner) = None;
+            self.lock.released.notify_all();
+        }
+    }
+}
+
+/// one lock per cache key, so concurrent `init`s of the same key resolve it only once.
+/// it is re-entrant: a `<clinit>` run by `find_class` that calls back into a native method
+/// initializing the same key on the same thread goes on instead of deadlocking.
+/// a `<clinit>` on another thread still can, see `SpClass::init` on deadlocks
+fn init_lock(kind: InitKind, key: i32) -> InitGuard {
+    let lock = INIT_LOCKS
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+        .entry((kind, key))
+        .or_default()
+        .clone();
+    let current = thread::current().id();
+    let mut owner = lock.owner.lock().unwrap_or_else(PoisonError::into_inner);
+    let owned = *owner != Some(current);
+    if owned {
+        while owner.is_some() {
+            owner = lock
+                .released
+                .wait(owner)
+                .unwrap_or_else(PoisonError::into_inner);
+        }
+        *owner = Some(current);
+    }
+    drop(owner);
+    InitGuard { lock, owned }
+}
+
+/// `init field java/lang/Integer.value I` for a failed `get_*_id`,
+/// `init field value I` without a declaring class
+fn member_context(kind: &str, class: &Option<String>, name: &str, sig: &str) -> String {
+    match class {
+        Some(class) => format!("init {} {}.{} {}", kind, class, name, sig),
+        None => format!("init {} {} {}", kind, name, sig),
+    }
+}
 
 pub struct SpStaticField {
     cache: StaticFieldKey,
     name: Option<String>,
+    class: Option<String>,
     ret: Option<String>,
 }
 
@@ -37,10 +386,16 @@ impl SpStaticField {
         STATIC_FIELD_CACHE.contains_key(&key)
     }
 
+    /// drop the cached id of `key`, `call` on it fails with a cache miss until the next `init`
+    pub fn forget(key: StaticFieldKey) {
+        STATIC_FIELD_CACHE.invalidate(&key);
+    }
+
     pub fn cache(key: StaticFieldKey) -> Self {
         Self {
             cache: key,
             name: None,
+            class: None,
             ret: None,
         }
     }
@@ -48,41 +403,135 @@ impl SpStaticField {
         Self {
             cache: key,
             name: Some(name.to_string()),
+            class: None,
             ret: Some(return_type.to_string()),
         }
     }
 
+    /// record the class this member belongs to (`java/lang/String` or `java.lang.String`),
+    /// only used for diagnostics and `init` errors
+    pub fn with_declaring_class(mut self, class: &str) -> Self {
+        self.class = Some(class.replace('.', "/"));
+        self
+    }
+
+    /// the class given to `with_declaring_class`, in `java/lang/String` form
+    pub fn declaring_class(&self) -> Option<&str> {
+        self.class.as_deref()
+    }
+
+    /// holds the key's init lock across `get_static_field_id`, see `SpClass::init` on deadlocks
     pub fn init(&self, env: &mut JNIEnv, jclass: &JClass) -> Result<()> {
+        if STATIC_FIELD_CACHE.contains_key(&self.cache) {
+            return Ok(());
+        }
+        let _guard = init_lock(InitKind::StaticField, self.cache);
         if STATIC_FIELD_CACHE.contains_key(&self.cache) {
             return Ok(());
         }
         let raw_id = match (&self.name, &self.ret) {
-            (Some(name), Some(sig)) => env.get_static_field_id(jclass, name, sig)?.into_raw(),
+            (Some(name), Some(sig)) => {
+                trace_miss!(
+                    "static field cache miss: key={}, name={}, sig={}",
+                    self.cache,
+                    name,
+                    sig
+                );
+                env.get_static_field_id(jclass, name, sig)
+                    .with_context(|| member_context("static field", &self.class, name, sig))?
+                    .into_raw()
+            }
             _ => return throw("init static field error: name or return type is null"),
         };
         STATIC_FIELD_CACHE.insert(self.cache, raw_id as usize);
         Ok(())
     }
 
-    pub fn call<'s>(
+    pub fn call<'local>(
         &self,
-        env: &'s mut JNIEnv,
+        env: &mut JNIEnv<'local>,
         class: &JClass,
         ret: JavaType,
-    ) -> Result<JValueGen<JObject<'s>>> {
-        let field_id = match FIELD_CACHE.get(&self.cache) {
+    ) -> Result<JValueGen<JObject<'local>>> {
+        let field_id = match STATIC_FIELD_CACHE.get(&self.cache) {
             Some(id) => unsafe { JStaticFieldID::from_raw(id as jfieldID) },
             None => return throw("no method cache"),
         };
         let result = env.get_static_field_unchecked(class, field_id, ret)?;
         Ok(result)
     }
+
+    /// read an object field once and keep the value as a `GlobalRef` in
+    /// `STATIC_FIELD_VALUE_CACHE`, later calls return it until `invalidate_value`.
+    /// a `null` value is not kept
+    pub fn call_cached_object<'local>(
+        &self,
+        env: &mut JNIEnv<'local>,
+        class: &JClass,
+        ret: JavaType,
+    ) -> Result<JObject<'local>> {
+        if let Some(value) = STATIC_FIELD_VALUE_CACHE.get(&self.cache) {
+            return Ok(env.new_local_ref(&value)?);
+        }
+        let value = self.call(env, class, ret)?.l()?;
+        if !value.is_null() {
+            STATIC_FIELD_VALUE_CACHE.insert(self.cache, env.new_global_ref(&value)?);
+        }
+        Ok(value)
+    }
+
+    /// forget the value kept by `call_cached_object`, the next call reads the field again
+    pub fn invalidate_value(key: StaticFieldKey) {
+        STATIC_FIELD_VALUE_CACHE.invalidate(&key);
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum FieldKind {
+    /// read the field on every `call`
+    #[default]
+    Plain,
+    /// `init` checks that the field is `final`. the first value read is kept by the handle
+    /// (objects as `GlobalRef`) with a weak reference to its object, and returned again while
+    /// `call` is made on that object. a kept read is one `IsSameObject` call (and a `NewLocalRef`
+    /// for an object value) in place of the field read
+    Final,
+    /// `init` checks that the field is `volatile`, every `call` reads it.
+    /// jni field access already has volatile semantics, nothing is kept
+    Volatile,
+}
+
+impl FieldKind {
+    /// the `java.lang.reflect.Modifier` bit the field must have
+    fn modifier(self) -> Option<(i32, &'static str)> {
+        match self {
+            FieldKind::Plain => None,
+            FieldKind::Final => Some((0x10, "final")),
+            FieldKind::Volatile => Some((0x40, "volatile")),
+        }
+    }
+}
+
+/// `getModifiers()` of the `java.lang.reflect.Field` of `id`
+fn field_modifiers(env: &mut JNIEnv, jclass: &JClass, id: JFieldID) -> Result<i32> {
+    let raw = env.get_raw();
+    let to_reflected_field = unsafe { (**raw).ToReflectedField }
+        .ok_or_else(|| anyhow!("ToReflectedField is missing from the jni function table"))?;
+    let field = unsafe { to_reflected_field(raw, jclass.as_raw(), id.into_raw(), JNI_FALSE) };
+    if field.is_null() {
+        return throw("ToReflectedField returned null");
+    }
+    let field = env.auto_local(unsafe { JObject::from_raw(field) });
+    Ok(env.call_method(&field, "getModifiers", "()I", &[])?.i()?)
 }
 
 pub struct SpField {
     cache: FieldKey,
     name: Option<String>,
+    class: Option<String>,
     ret: Option<String>,
+    kind: FieldKind,
+    final_value: Mutex<Option<(WeakRef, JValueGen<GlobalRef>)>>,
 }
 
 impl SpField {
@@ -90,53 +539,257 @@ impl SpField {
         FIELD_CACHE.contains_key(&key)
     }
 
+    /// drop the cached id of `key`, `call` on it fails with a cache miss until the next `init`
+    pub fn forget(key: FieldKey) {
+        FIELD_CACHE.invalidate(&key);
+    }
+
     pub fn cache(key: FieldKey) -> Self {
+        Self::cache_with_kind(key, FieldKind::Plain)
+    }
+
+    pub fn cache_with_kind(key: FieldKey, kind: FieldKind) -> Self {
         Self {
             cache: key,
             name: None,
+            class: None,
             ret: None,
+            kind,
+            final_value: Mutex::new(None),
         }
     }
 
     pub fn new(key: FieldKey, name: &str, return_type: &SpType) -> Self {
+        Self::new_with_kind(key, name, return_type, FieldKind::Plain)
+    }
+
+    pub fn new_with_kind(key: FieldKey, name: &str, return_type: &SpType, kind: FieldKind) -> Self {
         Self {
             cache: key,
             name: Some(name.to_string()),
+            class: None,
             ret: Some(return_type.to_string()),
+            kind,
+            final_value: Mutex::new(None),
         }
     }
 
+    /// record the class this member belongs to (`java/lang/String` or `java.lang.String`),
+    /// only used for diagnostics and `init` errors
+    pub fn with_declaring_class(mut self, class: &str) -> Self {
+        self.class = Some(class.replace('.', "/"));
+        self
+    }
+
+    pub fn kind(&self) -> FieldKind {
+        self.kind
+    }
+
+    /// the class given to `with_declaring_class`, in `java/lang/String` form
+    pub fn declaring_class(&self) -> Option<&str> {
+        self.class.as_deref()
+    }
+
+    /// holds the key's init lock across `get_field_id`, see `SpClass::init` on deadlocks
     pub fn init(&self, env: &mut JNIEnv, jclass: &JClass) -> Result<()> {
         if FIELD_CACHE.contains_key(&self.cache) {
             return Ok(());
         }
-        let raw_id = match (&self.name, &self.ret) {
-            (Some(name), Some(sig)) => env.get_field_id(jclass, name, sig)?.into_raw(),
+        let _guard = init_lock(InitKind::Field, self.cache);
+        if FIELD_CACHE.contains_key(&self.cache) {
+            return Ok(());
+        }
+        let (raw_id, sig) = match (&self.name, &self.ret) {
+            (Some(name), Some(sig)) => {
+                trace_miss!(
+                    "field cache miss: key={}, name={}, sig={}",
+                    self.cache,
+                    name,
+                    sig
+                );
+                let id = env
+                    .get_field_id(jclass, name, sig)
+                    .with_context(|| member_context("field", &self.class, name, sig))?;
+                if let Some((bit, modifier)) = self.kind.modifier()
+                    && field_modifiers(env, jclass, id)? & bit == 0
+                {
+                    return Err(anyhow!(
+                        "field kind error: key={}, name={} is not {}",
+                        self.cache,
+                        name,
+                        modifier
+                    ));
+                }
+                (id.into_raw(), sig)
+            }
             _ => return throw("init field error: name or return type is null"),
         };
-        FIELD_CACHE.insert(self.cache, raw_id as usize);
+        let entry = FieldEntry {
+            id: raw_id as usize,
+            tag: sig.as_bytes()[0],
+        };
+        FIELD_CACHE.insert(self.cache, entry);
         Ok(())
     }
 
-    pub fn call<'s>(
+    /// read the field of `this`, a `GlobalRef` receiver is passed as `&global` (it derefs to `&JObject`)
+    pub fn call<'local>(
         &self,
-        env: &'s mut JNIEnv,
+        env: &mut JNIEnv<'local>,
         this: &JObject,
         ret: ReturnType,
-    ) -> Result<JValueGen<JObject<'s>>> {
-        let field_id = match FIELD_CACHE.get(&self.cache) {
-            Some(id) => unsafe { JFieldID::from_raw(id as jfieldID) },
-            None => return throw("no method cache"),
-        };
+    ) -> Result<JValueGen<JObject<'local>>> {
+        if self.kind == FieldKind::Final {
+            return self.call_final(env, this, ret);
+        }
+        let field_id = self.field_id()?;
         let result = env.get_field_unchecked(this, field_id, ret)?;
         Ok(result)
     }
+
+    fn call_final<'local>(
+        &self,
+        env: &mut JNIEnv<'local>,
+        this: &JObject,
+        ret: ReturnType,
+    ) -> Result<JValueGen<JObject<'local>>> {
+        let mut final_value = self
+            .final_value
+            .lock()
+            .map_err(|_| anyhow!("final field value lock poisoned"))?;
+        if let Some((owner, value)) = final_value.as_ref()
+            && owner.is_same_object(env, this)?
+        {
+            return map_jvalue_object(value, |obj| Ok(env.new_local_ref(obj)?));
+        }
+        let field_id = self.field_id()?;
+        let result = env.get_field_unchecked(this, field_id, ret)?;
+        let stored = map_jvalue_object(&result, |obj| Ok(env.new_global_ref(obj)?))?;
+        *final_value = env.new_weak_ref(this)?.map(|owner| (owner, stored));
+        Ok(result)
+    }
+
+    /// write the field of `this`, a `GlobalRef` receiver is passed as `&global` like in `call`.
+    /// a `Final` handle forgets the value it kept,
+    /// `value` must match the field type, an `int` field only takes `JValue::Int`
+    pub fn set(&self, env: &mut JNIEnv, this: &JObject, value: JValue) -> Result<()> {
+        let field_id = self.checked_field_id(&value)?;
+        env.set_field_unchecked(this, field_id, value)?;
+        self.clear_final();
+        Ok(())
+    }
+
+    /// a write makes the cached value of a `Final` field stale
+    fn clear_final(&self) {
+        if self.kind == FieldKind::Final
+            && let Ok(mut final_value) = self.final_value.lock()
+        {
+            *final_value = None;
+        }
+    }
+
+    pub fn get_bool(&self, env: &mut JNIEnv, this: &JObject) -> Result<bool> {
+        let value = self.call(env, this, ReturnType::Primitive(Primitive::Boolean))?;
+        bool::from_jvalue(value)
+    }
+
+    fn field_id(&self) -> Result<JFieldID> {
+        match FIELD_CACHE.get(&self.cache) {
+            Some(entry) => Ok(unsafe { JFieldID::from_raw(entry.id as jfieldID) }),
+            None => throw("no method cache"),
+        }
+    }
+
+    /// `field_id`, after checking that `value` can be written to the field
+    fn checked_field_id(&self, value: &JValue) -> Result<JFieldID> {
+        let entry = match FIELD_CACHE.get(&self.cache) {
+            Some(entry) => entry,
+            None => return throw("no method cache"),
+        };
+        let matches = matches!(
+            (entry.tag, value),
+            (b'L' | b'[', JValueGen::Object(_))
+                | (b'Z', JValueGen::Bool(_))
+                | (b'B', JValueGen::Byte(_))
+                | (b'C', JValueGen::Char(_))
+                | (b'S', JValueGen::Short(_))
+                | (b'I', JValueGen::Int(_))
+                | (b'J', JValueGen::Long(_))
+                | (b'F', JValueGen::Float(_))
+                | (b'D', JValueGen::Double(_))
+        );
+        if !matches {
+            return Err(anyhow!(
+                "field type error: key={} is {}, got {}",
+                self.cache,
+                entry.tag as char,
+                value.type_name()
+            ));
+        }
+        Ok(unsafe { JFieldID::from_raw(entry.id as jfieldID) })
+    }
+}
+
+/// read many fields of one object, all field ids are looked up before the first read
+pub fn read_fields<'local>(
+    env: &mut JNIEnv<'local>,
+    this: &JObject,
+    fields: &[&SpField],
+    rets: &[ReturnType],
+) -> Result<Vec<JValueGen<JObject<'local>>>> {
+    if fields.len() != rets.len() {
+        return Err(anyhow!(
+            "read fields error: {} fields but {} return types",
+            fields.len(),
+            rets.len()
+        ));
+    }
+    let ids = fields
+        .iter()
+        .map(|field| field.field_id())
+        .collect::<Result<Vec<_>>>()?;
+    let mut result = Vec::with_capacity(ids.len());
+    for (id, ret) in ids.into_iter().zip(rets) {
+        result.push(env.get_field_unchecked(this, id, ret.clone())?);
+    }
+    Ok(result)
+}
+
+/// write many fields of one object, all field ids are looked up and every value is type checked
+/// before the first write, stops at the first failed write
+pub fn write_fields(env: &mut JNIEnv, this: &JObject, writes: &[(&SpField, JValue)]) -> Result<()> {
+    let ids = writes
+        .iter()
+        .map(|(field, value)| field.checked_field_id(value))
+        .collect::<Result<Vec<_>>>()?;
+    for (id, (field, value)) in ids.into_iter().zip(writes) {
+        env.set_field_unchecked(this, id, *value)?;
+        field.clear_final();
+    }
+    Ok(())
+}
+
+/// a resolved field id and the first byte of its descriptor (`I`, `L`, `[` ...),
+/// checked against the value of `SpField::set`
+#[derive(Clone, Copy, Debug)]
+pub struct FieldEntry {
+    pub id: usize,
+    pub tag: u8,
+}
+
+/// a resolved method id and its parameter count, checked against the arguments of `call`
+#[derive(Clone, Copy, Debug)]
+pub struct MethodEntry {
+    pub id: usize,
+    pub arity: usize,
 }
 
 pub struct SpStaticMethod {
     cache: StaticMethodKey,
     name: Option<String>,
+    class: Option<String>,
     sig: Option<String>,
+    arity: usize,
 }
 
 impl SpStaticMethod {
@@ -144,66 +797,113 @@ impl SpStaticMethod {
         STATIC_METHOD_CACHE.contains_key(&key)
     }
 
+    /// drop the cached id of `key`, `call` on it fails with a cache miss until the next `init`
+    pub fn forget(key: StaticMethodKey) {
+        STATIC_METHOD_CACHE.invalidate(&key);
+    }
+
     pub fn cache(key: StaticMethodKey) -> Self {
         Self {
             cache: key,
             name: None,
+            class: None,
             sig: None,
+            arity: 0,
         }
     }
 
     pub fn new(key: StaticMethodKey, name: &str, return_type: &SpType, args: &[SpType]) -> Self {
-        let mut all_len = return_type.get_str_len() + 2;
-        for n in args {
-            all_len += n.get_str_len();
-        }
-        let mut sig_builder = String::with_capacity(all_len);
-        sig_builder.push('(');
-        for n in args {
-            sig_builder.push_str(&n.to_string());
-        }
-        sig_builder.push(')');
-        sig_builder.push_str(&return_type.to_string());
+        let sig_builder = build_signature(return_type, args);
 
         Self {
             cache: key,
             name: Some(name.to_string()),
+            class: None,
             sig: Some(sig_builder),
+            arity: args.len(),
         }
     }
 
+    /// record the class this member belongs to (`java/lang/String` or `java.lang.String`),
+    /// only used for diagnostics and `init` errors
+    pub fn with_declaring_class(mut self, class: &str) -> Self {
+        self.class = Some(class.replace('.', "/"));
+        self
+    }
+
+    /// the class given to `with_declaring_class`, in `java/lang/String` form
+    pub fn declaring_class(&self) -> Option<&str> {
+        self.class.as_deref()
+    }
+
+    /// holds the key's init lock across `get_static_method_id`, see `SpClass::init` on deadlocks
     pub fn init(&self, env: &mut JNIEnv, jclass: &JClass) -> Result<()> {
+        if STATIC_METHOD_CACHE.contains_key(&self.cache) {
+            return Ok(());
+        }
+        let _guard = init_lock(InitKind::StaticMethod, self.cache);
         if STATIC_METHOD_CACHE.contains_key(&self.cache) {
             return Ok(());
         }
         let raw_id = match (&self.name, &self.sig) {
-            (Some(name), Some(sig)) => env.get_static_method_id(jclass, name, sig)?.into_raw(),
+            (Some(name), Some(sig)) => {
+                trace_miss!(
+                    "static method cache miss: key={}, name={}, sig={}",
+                    self.cache,
+                    name,
+                    sig
+                );
+                env.get_static_method_id(jclass, name, sig)
+                    .with_context(|| member_context("static method", &self.class, name, sig))?
+                    .into_raw()
+            }
             _ => return throw("init static method error: name or sig is null"),
         };
-        STATIC_METHOD_CACHE.insert(self.cache, raw_id as usize);
+        let entry = MethodEntry {
+            id: raw_id as usize,
+            arity: self.arity,
+        };
+        STATIC_METHOD_CACHE.insert(self.cache, entry);
         Ok(())
     }
 
-    pub fn call<'s>(
+    pub fn call<'local>(
         &self,
-        env: &'s mut JNIEnv,
+        env: &mut JNIEnv<'local>,
         class: &JClass,
         args: &[jvalue],
         ret: ReturnType,
-    ) -> Result<JValueGen<JObject<'s>>> {
-        let method_id = match STATIC_METHOD_CACHE.get(&self.cache) {
-            Some(id) => unsafe { JStaticMethodID::from_raw(id as jmethodID) },
+    ) -> Result<JValueGen<JObject<'local>>> {
+        let entry = match STATIC_METHOD_CACHE.get(&self.cache) {
+            Some(entry) => entry,
             None => return throw("no method cache"),
         };
-        let result = unsafe { env.call_static_method_unchecked(class, method_id, ret, args)? };
-        Ok(result)
+        if args.len() != entry.arity {
+            return Err(anyhow!(
+                "static method arity error: key={} expects {} arguments, got {}",
+                self.cache,
+                entry.arity,
+                args.len()
+            ));
+        }
+        let method_id = unsafe { JStaticMethodID::from_raw(entry.id as jmethodID) };
+        #[cfg(feature = "metrics")]
+        let start = std::time::Instant::now();
+        let result = unsafe { env.call_static_method_unchecked(class, method_id, ret, args) };
+        #[cfg(feature = "metrics")]
+        crate::metrics::record_static_method(self.cache, start.elapsed());
+        Ok(result?)
     }
+
+    typed_calls!(class: &JClass);
 }
 
 pub struct SpMethod {
     cache: MethodKey,
     name: Option<String>,
+    class: Option<String>,
     sig: Option<String>,
+    arity: usize,
 }
 
 impl SpMethod {
@@ -211,61 +911,270 @@ impl SpMethod {
         METHOD_CACHE.contains_key(&key)
     }
 
+    /// drop the cached id of `key`, `call` on it fails with a cache miss until the next `init`
+    pub fn forget(key: MethodKey) {
+        METHOD_CACHE.invalidate(&key);
+    }
+
     pub fn cache(key: MethodKey) -> Self {
         Self {
             cache: key,
             name: None,
+            class: None,
             sig: None,
+            arity: 0,
         }
     }
     pub fn new(key: MethodKey, name: &str, return_type: &SpType, args: &[SpType]) -> Self {
-        let mut all_len = return_type.get_str_len() + 2;
-        for n in args {
-            all_len += n.get_str_len();
-        }
-        let mut sig_builder = String::with_capacity(all_len);
-        sig_builder.push('(');
-        for n in args {
-            sig_builder.push_str(&n.to_string());
-        }
-        sig_builder.push(')');
-        sig_builder.push_str(&return_type.to_string());
+        let sig_builder = build_signature(return_type, args);
 
         Self {
             cache: key,
             name: Some(name.to_string()),
+            class: None,
             sig: Some(sig_builder),
+            arity: args.len(),
         }
     }
 
+    /// record the class this member belongs to (`java/lang/String` or `java.lang.String`),
+    /// only used for diagnostics and `init` errors
+    pub fn with_declaring_class(mut self, class: &str) -> Self {
+        self.class = Some(class.replace('.', "/"));
+        self
+    }
+
+    /// the class given to `with_declaring_class`, in `java/lang/String` form
+    pub fn declaring_class(&self) -> Option<&str> {
+        self.class.as_deref()
+    }
+
+    /// holds the key's init lock across `get_method_id`, see `SpClass::init` on deadlocks
     pub fn init(&self, env: &mut JNIEnv, jclass: &JClass) -> Result<()> {
+        if METHOD_CACHE.contains_key(&self.cache) {
+            return Ok(());
+        }
+        let _guard = init_lock(InitKind::Method, self.cache);
         if METHOD_CACHE.contains_key(&self.cache) {
             return Ok(());
         }
         let raw_id = match (&self.name, &self.sig) {
-            (Some(name), Some(sig)) => env.get_method_id(jclass, name, sig)?.into_raw(),
+            (Some(name), Some(sig)) => {
+                trace_miss!(
+                    "method cache miss: key={}, name={}, sig={}",
+                    self.cache,
+                    name,
+                    sig
+                );
+                env.get_method_id(jclass, name, sig)
+                    .with_context(|| member_context("method", &self.class, name, sig))?
+                    .into_raw()
+            }
             _ => return throw("init static method error: name or sig is null"),
         };
-        METHOD_CACHE.insert(self.cache, raw_id as usize);
+        let entry = MethodEntry {
+            id: raw_id as usize,
+            arity: self.arity,
+        };
+        METHOD_CACHE.insert(self.cache, entry);
         Ok(())
     }
 
-    pub fn call<'s>(
+    /// `new` + `init` of the first parameter list in `candidates` that `jclass` has a method
+    /// `name` for, like picking `add(I)V` or `add(Ljava/lang/Object;)V`. the
+    /// `NoSuchMethodError`s of the candidates before it are cleared.
+    /// a `key` that is already cached keeps its method, the candidate returned is the one whose
+    /// `get_method_id` is the cached id, and none of them matching is an error
+    pub fn resolve_overload(
+        env: &mut JNIEnv,
+        jclass: &JClass,
+        key: MethodKey,
+        name: &str,
+        return_type: &SpType,
+        candidates: &[&[SpType]],
+    ) -> Result<Self> {
+        if let Some(entry) = METHOD_CACHE.get(&key) {
+            for args in candidates.iter().filter(|args| args.len() == entry.arity) {
+                let method = Self::new(key, name, return_type, args);
+                let sig = method.sig.as_deref().unwrap_or_default();
+                match env.get_method_id(jclass, name, sig) {
+                    Ok(id) if id.into_raw() as usize == entry.id => return Ok(method),
+                    Ok(_) => {}
+                    Err(_) => {
+                        if env.exception_check()? {
+                            env.exception_clear()?;
+                        }
+                    }
+                }
+            }
+            return Err(anyhow!(
+                "method key={} is cached for a method that is no overload of {} in candidates",
+                key,
+                name
+            ));
+        }
+        let mut errors = Vec::with_capacity(candidates.len());
+        for args in candidates {
+            let method = Self::new(key, name, return_type, args);
+            match method.init(env, jclass) {
+                Ok(()) => return Ok(method),
+                Err(e) => {
+                    if env.exception_check()? {
+                        env.exception_clear()?;
+                    }
+                    errors.push(format!(
+                        "{}: {}",
+                        method.sig.as_deref().unwrap_or_default(),
+                        e
+                    ));
+                }
+            }
+        }
+        Err(anyhow!(
+            "no overload of {} found: [{}]",
+            name,
+            errors.join(", ")
+        ))
+    }
+
+    /// virtual dispatch on `this`: a method resolved on an interface (a default method too)
+    /// runs the implementation of the class of `this`, or the interface default if it has none.
+    /// a receiver kept as `GlobalRef` is passed as is, `&global` derefs to `&JObject`
+    pub fn call<'local>(
         &self,
-        env: &'s mut JNIEnv,
+        env: &mut JNIEnv<'local>,
         this: &JObject,
         args: &[jvalue],
         ret: ReturnType,
-    ) -> Result<JValueGen<JObject<'s>>> {
-        let method_id = match METHOD_CACHE.get(&self.cache) {
-            Some(id) => unsafe { JMethodID::from_raw(id as jmethodID) },
+    ) -> Result<JValueGen<JObject<'local>>> {
+        let entry = match METHOD_CACHE.get(&self.cache) {
+            Some(entry) => entry,
             None => return throw("no method cache"),
         };
-        let result = unsafe { env.call_method_unchecked(this, method_id, ret, args)? };
-        Ok(result)
+        if args.len() != entry.arity {
+            return Err(anyhow!(
+                "method arity error: key={} expects {} arguments, got {}",
+                self.cache,
+                entry.arity,
+                args.len()
+            ));
+        }
+        let method_id = unsafe { JMethodID::from_raw(entry.id as jmethodID) };
+        #[cfg(feature = "metrics")]
+        let start = std::time::Instant::now();
+        let result = unsafe { env.call_method_unchecked(this, method_id, ret, args) };
+        #[cfg(feature = "metrics")]
+        crate::metrics::record_method(self.cache, start.elapsed());
+        Ok(result?)
+    }
+
+    /// `call` of a method without parameters, a method that takes any is an error
+    pub fn call_noargs<'local>(
+        &self,
+        env: &mut JNIEnv<'local>,
+        this: &JObject,
+        ret: ReturnType,
+    ) -> Result<JValueGen<JObject<'local>>> {
+        let entry = match METHOD_CACHE.get(&self.cache) {
+            Some(entry) => entry,
+            None => return throw("no method cache"),
+        };
+        if entry.arity != 0 {
+            return Err(anyhow!(
+                "call_noargs on method key={} with {} parameters",
+                self.cache,
+                entry.arity
+            ));
+        }
+        self.call(env, this, &[], ret)
+    }
+
+    typed_calls!(this: &JObject);
+
+    /// same as `call` with object arguments only, `None` is passed as `null`.
+    /// for a handle made by `new` every parameter is checked to be an object or array type
+    pub fn call_nullable<'local>(
+        &self,
+        env: &mut JNIEnv<'local>,
+        this: &JObject,
+        args: &[Option<&JObject>],
+        ret: ReturnType,
+    ) -> Result<JValueGen<JObject<'local>>> {
+        if let Some(sig) = &self.sig {
+            let (params, _) = parse_method_descriptor(sig)?;
+            if params.len() != args.len() {
+                return Err(anyhow!(
+                    "method arity error: {} expects {} arguments, got {}",
+                    sig,
+                    params.len(),
+                    args.len()
+                ));
+            }
+            if let Some((i, t)) = params
+                .iter()
+                .enumerate()
+                .find(|(_, t)| !matches!(t, SpType::Class(_) | SpType::Array(_)))
+            {
+                return Err(anyhow!(
+                    "argument {} of {} is primitive {}, can not be an object",
+                    i,
+                    sig,
+                    t
+                ));
+            }
+        }
+        let args: Vec<jvalue> = args.iter().map(|arg| object_arg(*arg)).collect();
+        self.call(env, this, &args, ret)
+    }
+
+    /// same as `call`, every argument is passed as an object reference
+    pub fn call_global<'local>(
+        &self,
+        env: &mut JNIEnv<'local>,
+        this: &JObject,
+        args: &[&GlobalRef],
+        ret: ReturnType,
+    ) -> Result<JValueGen<JObject<'local>>> {
+        let args: Vec<jvalue> = args
+            .iter()
+            .map(|arg| JValue::Object(arg.as_obj()).as_jni())
+            .collect();
+        self.call(env, this, &args, ret)
     }
 }
 
+/// `new Class(ctor_args)` with the `<init>` method `ctor`, then write `field_sets` on the new object
+pub fn construct_and_set<'local>(
+    env: &mut JNIEnv<'local>,
+    class: &SpClass,
+    ctor: &SpMethod,
+    ctor_args: &[jvalue],
+    field_sets: &[(&SpField, JValue)],
+) -> Result<JObject<'local>> {
+    let class_ref = class.class_ref()?;
+    let entry = match METHOD_CACHE.get(&ctor.cache) {
+        Some(entry) => entry,
+        None => return throw("no method cache"),
+    };
+    if ctor_args.len() != entry.arity {
+        return Err(anyhow!(
+            "constructor arity error: key={} expects {} arguments, got {}",
+            ctor.cache,
+            entry.arity,
+            ctor_args.len()
+        ));
+    }
+    let ctor_id = unsafe { JMethodID::from_raw(entry.id as jmethodID) };
+    let obj = unsafe {
+        env.new_object_unchecked(<&JClass>::from(class_ref.as_obj()), ctor_id, ctor_args)?
+    };
+    for (field, value) in field_sets {
+        field.set(env, &obj, *value)?;
+    }
+    Ok(obj)
+}
+
+#[derive(PartialEq, Eq, Hash, Default)]
 pub enum SpType {
     Byte,
     Char,
@@ -275,6 +1184,7 @@ pub enum SpType {
     Long,
     Sort,
     Boolean,
+    #[default]
     Void,
     Class(SpClass),
     Array(Box<SpType>),
@@ -286,6 +1196,16 @@ impl SpType {
         Self::Class(c)
     }
 
+    /// `array_of(SpType::Int, 2)` is `int[][]` (`[[I`), `dims` must be at least 1
+    pub fn array_of(element: SpType, dims: usize) -> Self {
+        assert!(dims >= 1, "array dims must be at least 1");
+        let mut t = element;
+        for _ in 0..dims {
+            t = Self::Array(Box::new(t));
+        }
+        t
+    }
+
     pub fn get_str_len(&self) -> usize {
         match self {
             Self::Class(class) => {
@@ -300,12 +1220,341 @@ impl SpType {
             _ => 1,
         }
     }
+
+    /// append the descriptor to `out`, same output as `Display` without the temporary strings
+    pub fn write_descriptor(&self, out: &mut String) {
+        match self {
+            Self::Class(class) => {
+                out.push('L');
+                out.push_str(
+                    class
+                        .class_full_path
+                        .as_deref()
+                        .unwrap_or("java/lang/Object"),
+                );
+                out.push(';');
+            }
+            Self::Array(t) => {
+                out.push('[');
+                t.write_descriptor(out);
+            }
+            Self::Byte => out.push('B'),
+            Self::Char => out.push('C'),
+            Self::Double => out.push('D'),
+            Self::Float => out.push('F'),
+            Self::Int => out.push('I'),
+            Self::Long => out.push('J'),
+            Self::Sort => out.push('S'),
+            Self::Boolean => out.push('Z'),
+            Self::Void => out.push('V'),
+        }
+    }
+
+    fn primitive(&self) -> Option<Primitive> {
+        match self {
+            Self::Byte => Some(Primitive::Byte),
+            Self::Char => Some(Primitive::Char),
+            Self::Double => Some(Primitive::Double),
+            Self::Float => Some(Primitive::Float),
+            Self::Int => Some(Primitive::Int),
+            Self::Long => Some(Primitive::Long),
+            Self::Sort => Some(Primitive::Short),
+            Self::Boolean => Some(Primitive::Boolean),
+            Self::Void => Some(Primitive::Void),
+            Self::Class(_) | Self::Array(_) => None,
+        }
+    }
+
+    /// the `ret` argument of `call` for a method returning this type,
+    /// `Void` is `Primitive::Void` so the call reads no value
+    pub fn to_return_type(&self) -> ReturnType {
+        match self {
+            Self::Class(_) => ReturnType::Object,
+            Self::Array(_) => ReturnType::Array,
+            Self::Void => ReturnType::Primitive(Primitive::Void),
+            t => ReturnType::Primitive(t.primitive().unwrap_or(Primitive::Void)),
+        }
+    }
+
+    /// the `ret` argument of `SpStaticField::call` for a field of this type
+    pub fn to_java_type(&self) -> JavaType {
+        match self {
+            Self::Class(class) => JavaType::Object(
+                class
+                    .class_full_path
+                    .as_deref()
+                    .unwrap_or("java/lang/Object")
+                    .to_string(),
+            ),
+            Self::Array(t) => JavaType::Array(Box::new(t.to_java_type())),
+            t => JavaType::Primitive(t.primitive().unwrap_or(Primitive::Void)),
+        }
+    }
+
+    /// the name as written in java source: `int`, `java.lang.String`, `int[][]`, for logs and
+    /// generated code. `Display` is the jvm descriptor instead (`I`, `Ljava/lang/String;`, `[[I`)
+    pub fn java_source_name(&self) -> String {
+        match self {
+            Self::Byte => "byte".to_string(),
+            Self::Char => "char".to_string(),
+            Self::Double => "double".to_string(),
+            Self::Float => "float".to_string(),
+            Self::Int => "int".to_string(),
+            Self::Long => "long".to_string(),
+            Self::Sort => "short".to_string(),
+            Self::Boolean => "boolean".to_string(),
+            Self::Void => "void".to_string(),
+            Self::Class(class) => class
+                .class_full_path
+                .as_deref()
+                .unwrap_or("java/lang/Object")
+                .replace(['/', '$'], "."),
+            Self::Array(t) => format!("{}[]", t.java_source_name()),
+        }
+    }
+
+    /// parse a single type descriptor, like `I`, `Ljava/lang/String;` or `[[J`
+    pub fn from_descriptor(descriptor: &str) -> Result<Self> {
+        let (t, rest) = Self::parse_descriptor_prefix(descriptor)?;
+        if !rest.is_empty() {
+            return Err(anyhow!("trailing data in descriptor: {}", descriptor));
+        }
+        Ok(t)
+    }
+
+    fn parse_descriptor_prefix(descriptor: &str) -> Result<(Self, &str)> {
+        match descriptor.chars().next() {
+            Some('L') => {
+                let end = match descriptor.find(';') {
+                    Some(end) if end > 1 => end,
+                    _ => return Err(anyhow!("bad class descriptor: {}", descriptor)),
+                };
+                let class = Self::new_class(&descriptor[1..end]);
+                Ok((class, &descriptor[end + 1..]))
+            }
+            Some('[') => {
+                let (element, rest) = Self::parse_descriptor_prefix(&descriptor[1..])?;
+                if element == Self::Void {
+                    return throw("array element can not be void");
+                }
+                Ok((Self::Array(Box::new(element)), rest))
+            }
+            Some(c) => Ok((Self::try_from(c)?, &descriptor[1..])),
+            None => throw("empty descriptor"),
+        }
+    }
 }
 
-impl Default for SpType {
-    fn default() -> Self {
-        Self::Void
+/// the one char descriptor of a primitive (`I` -> `Int`), `L` and `[` need the whole descriptor,
+/// use `SpType::from_descriptor` for them
+impl TryFrom<char> for SpType {
+    type Error = anyhow::Error;
+
+    fn try_from(c: char) -> Result<Self> {
+        match c {
+            'B' => Ok(Self::Byte),
+            'C' => Ok(Self::Char),
+            'D' => Ok(Self::Double),
+            'F' => Ok(Self::Float),
+            'I' => Ok(Self::Int),
+            'J' => Ok(Self::Long),
+            'S' => Ok(Self::Sort),
+            'Z' => Ok(Self::Boolean),
+            'V' => Ok(Self::Void),
+            'L' | '[' => Err(anyhow!(
+                "{} is not a one char descriptor, use from_descriptor",
+                c
+            )),
+            c => Err(anyhow!("unknown descriptor type: {}", c)),
+        }
+    }
+}
+
+/// rust types with a fixed java type, see `java_sig!`
+pub trait JavaMapped {
+    fn sp_type() -> SpType;
+}
+
+macro_rules! impl_java_mapped {
+    ($($t:ty => $sp:expr),* $(,)?) => {
+        $(
+            impl JavaMapped for $t {
+                fn sp_type() -> SpType {
+                    $sp
+                }
+            }
+        )*
+    };
+}
+
+impl_java_mapped! {
+    i8 => SpType::Byte,
+    u8 => SpType::Byte,
+    u16 => SpType::Char,
+    char => SpType::Char,
+    f64 => SpType::Double,
+    f32 => SpType::Float,
+    i32 => SpType::Int,
+    i64 => SpType::Long,
+    i16 => SpType::Sort,
+    bool => SpType::Boolean,
+    () => SpType::Void,
+    String => SpType::new_class("java/lang/String"),
+    &str => SpType::new_class("java/lang/String"),
+}
+
+impl<T: JavaMapped> JavaMapped for Vec<T> {
+    fn sp_type() -> SpType {
+        SpType::Array(Box::new(T::sp_type()))
+    }
+}
+
+impl<T: JavaMapped> JavaMapped for &[T] {
+    fn sp_type() -> SpType {
+        SpType::Array(Box::new(T::sp_type()))
+    }
+}
+
+pub fn sp_type_of<T: JavaMapped>() -> SpType {
+    T::sp_type()
+}
+
+/// the method descriptor of a rust fn type:
+/// `java_sig!(fn(i32, String) -> bool)` is `(ILjava/lang/String;)Z`,
+/// `java_sig!(fn(Vec<u8>))` is `([B)V`
+#[macro_export]
+macro_rules! java_sig {
+    (fn($($arg:ty),* $(,)?) -> $ret:ty) => {
+        $crate::build_signature(
+            &<$ret as $crate::JavaMapped>::sp_type(),
+            &[$(<$arg as $crate::JavaMapped>::sp_type()),*],
+        )
+    };
+    (fn($($arg:ty),* $(,)?)) => {
+        $crate::java_sig!(fn($($arg),*) -> ())
+    };
+}
+
+/// end of the type starting at `i`, `None` if it is not a valid descriptor type
+const fn descriptor_type_end(bytes: &[u8], mut i: usize, mut allow_void: bool) -> Option<usize> {
+    while i < bytes.len() && bytes[i] == b'[' {
+        i += 1;
+        allow_void = false;
+    }
+    if i >= bytes.len() {
+        return None;
+    }
+    match bytes[i] {
+        b'B' | b'C' | b'D' | b'F' | b'I' | b'J' | b'S' | b'Z' => Some(i + 1),
+        b'V' if allow_void => Some(i + 1),
+        b'L' => {
+            let start = i + 1;
+            i = start;
+            while i < bytes.len() && bytes[i] != b';' {
+                if matches!(bytes[i], b'.' | b'(' | b')' | b'[') {
+                    return None;
+                }
+                i += 1;
+            }
+            if i >= bytes.len() || i == start {
+                None
+            } else {
+                Some(i + 1)
+            }
+        }
+        _ => None,
+    }
+}
+
+/// whether `desc` is a well formed field (`[I`) or method (`(I)Z`) descriptor, usable in const
+pub const fn is_valid_descriptor(desc: &str) -> bool {
+    let bytes = desc.as_bytes();
+    if bytes.is_empty() {
+        return false;
+    }
+    if bytes[0] != b'(' {
+        return matches!(descriptor_type_end(bytes, 0, false), Some(end) if end == bytes.len());
     }
+    let mut i = 1;
+    while i < bytes.len() && bytes[i] != b')' {
+        match descriptor_type_end(bytes, i, false) {
+            Some(end) => i = end,
+            None => return false,
+        }
+    }
+    if i >= bytes.len() {
+        return false;
+    }
+    matches!(descriptor_type_end(bytes, i + 1, true), Some(end) if end == bytes.len())
+}
+
+/// a hand written descriptor checked at compile time: `const_descriptor!("(I)Z")`,
+/// a malformed one like `const_descriptor!("(I")` fails the build
+///
+/// ```
+/// const IS_EMPTY: &str = jni_sp_util::const_descriptor!("(Ljava/lang/String;)Z");
+/// assert_eq!(IS_EMPTY, "(Ljava/lang/String;)Z");
+/// ```
+///
+/// ```compile_fail
+/// const BROKEN: &str = jni_sp_util::const_descriptor!("(I");
+/// ```
+#[macro_export]
+macro_rules! const_descriptor {
+    ($desc:expr) => {{
+        const DESC: &str = $desc;
+        const _: () = assert!($crate::is_valid_descriptor(DESC), "invalid jni descriptor");
+        DESC
+    }};
+}
+
+/// the java `native` declaration of a method, for build scripts that write the `.java` side:
+/// `java_native_decl("foo", true, &SpType::Long, &[("a", SpType::Int)])`
+/// is `public static native long foo(int a);`
+pub fn java_native_decl(
+    name: &str,
+    is_static: bool,
+    ret: &SpType,
+    params: &[(&str, SpType)],
+) -> String {
+    let params = params
+        .iter()
+        .map(|(name, t)| format!("{} {}", t.java_source_name(), name))
+        .collect::<Vec<_>>()
+        .join(", ");
+    format!(
+        "public {}native {} {}({});",
+        if is_static { "static " } else { "" },
+        ret.java_source_name(),
+        name,
+        params
+    )
+}
+
+/// parse a method descriptor like `(ILjava/lang/String;)Z` into parameter types and return type
+pub fn parse_method_descriptor(descriptor: &str) -> Result<(Vec<SpType>, SpType)> {
+    let Some(body) = descriptor.strip_prefix('(') else {
+        return Err(anyhow!(
+            "method descriptor must start with '(': {}",
+            descriptor
+        ));
+    };
+    let Some(end) = body.find(')') else {
+        return Err(anyhow!("method descriptor missing ')': {}", descriptor));
+    };
+
+    let mut params = Vec::new();
+    let mut rest = &body[..end];
+    while !rest.is_empty() {
+        let (param, next) = SpType::parse_descriptor_prefix(rest)?;
+        if param == SpType::Void {
+            return Err(anyhow!("void parameter in descriptor: {}", descriptor));
+        }
+        params.push(param);
+        rest = next;
+    }
+    let ret = SpType::from_descriptor(&body[end + 1..])?;
+    Ok((params, ret))
 }
 
 impl Display for SpType {
@@ -316,7 +1565,7 @@ impl Display for SpType {
             Self::Double => f.write_str("D"),
             Self::Float => f.write_str("F"),
             Self::Int => f.write_str("I"),
-            Self::Long => f.write_str("L"),
+            Self::Long => f.write_str("J"),
             Self::Sort => f.write_str("S"),
             Self::Boolean => f.write_str("Z"),
             Self::Void => f.write_str("V"),
@@ -328,7 +1577,7 @@ impl Display for SpType {
                     .as_deref()
                     .unwrap_or("java/lang/Object")
             ),
-            Self::Array(t) => write!(f, "[{}", t.to_string()),
+            Self::Array(t) => write!(f, "[{}", t),
         }
     }
 }
@@ -336,7 +1585,32 @@ impl Display for SpType {
 pub struct SpClass {
     cache: ClassKey,
     class_full_path: Option<String>,
-    jni_class_ref: Option<GlobalRef>,
+    jni_class_ref: OnceLock<GlobalRef>,
+    loaders: Vec<GlobalRef>,
+}
+
+/// Classes compare by their normalized internal name (`java/lang/Object`),
+/// the cache key and the resolved global ref are not part of the identity.
+/// a handle without a name (`SpClass::cache`) is identified by its key instead.
+impl PartialEq for SpClass {
+    fn eq(&self, other: &Self) -> bool {
+        match (&self.class_full_path, &other.class_full_path) {
+            (Some(this), Some(other)) => this == other,
+            (None, None) => self.cache == other.cache,
+            _ => false,
+        }
+    }
+}
+
+impl Eq for SpClass {}
+
+impl Hash for SpClass {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        match &self.class_full_path {
+            Some(path) => path.hash(state),
+            None => self.cache.hash(state),
+        }
+    }
 }
 
 impl SpClass {
@@ -349,15 +1623,20 @@ impl SpClass {
         Self {
             cache: -1,
             class_full_path: Some(path),
-            jni_class_ref: None,
+            jni_class_ref: OnceLock::new(),
+            loaders: Vec::new(),
         }
     }
 
+    /// a handle of the class cached under `key`, ready to use without `init` while the entry lives
     pub fn cache(key: ClassKey) -> Self {
         Self {
             cache: key,
             class_full_path: None,
-            jni_class_ref: None,
+            jni_class_ref: CLASS_CACHE
+                .get(&key)
+                .map_or_else(OnceLock::new, OnceLock::from),
+            loaders: Vec::new(),
         }
     }
     pub fn new(key: ClassKey, sig: &str) -> Self {
@@ -365,55 +1644,401 @@ impl SpClass {
         Self {
             cache: key,
             class_full_path: Some(path),
-            jni_class_ref: None,
+            jni_class_ref: OnceLock::new(),
+            loaders: Vec::new(),
         }
     }
 
-    pub fn init(&mut self, env: &mut JNIEnv) -> Result<()> {
-        if self.jni_class_ref.is_some() {
-            return Ok(());
+    /// the array class of `element`, `array_of(key, &SpType::Int)` is `int[]` (`[I`)
+    pub fn array_of(key: ClassKey, element: &SpType) -> Self {
+        assert!(*element != SpType::Void, "array element can not be void");
+        Self::new(key, &format!("[{}", element))
+    }
+
+    /// resolve the class with `ClassLoader.loadClass` of each loader in order instead of `find_class`,
+    /// the first loader that knows the class wins
+    pub fn new_with_loaders(key: ClassKey, sig: &str, loaders: &[GlobalRef]) -> Self {
+        let mut class = Self::new(key, sig);
+        class.loaders = loaders.to_vec();
+        class
+    }
+
+    /// `DefineClass` of generated bytecode `bytes` into `loader`, the class is cached under `key`.
+    /// defining a name twice in one loader is a `LinkageError`
+    pub fn define(
+        env: &mut JNIEnv,
+        key: ClassKey,
+        name: &str,
+        loader: &JObject,
+        bytes: &[u8],
+    ) -> Result<Self> {
+        if bytes.is_empty() {
+            return Err(anyhow!("define class {}: empty class bytes", name));
         }
-        if self.cache < 0 {
-            if self.class_full_path.is_none() {
-                return throw("no class");
+        let path = name.replace(".", "/");
+        let class = env.define_class(path.as_str(), loader, bytes)?;
+        let class_ref = env.new_global_ref(&class)?;
+        env.delete_local_ref(class)?;
+        cache_class(key, class_ref.clone());
+        Ok(Self {
+            cache: key,
+            class_full_path: Some(path),
+            jni_class_ref: OnceLock::from(class_ref),
+            loaders: Vec::new(),
+        })
+    }
+
+    /// the class object of a primitive type, like `int.class` (`Integer.TYPE`),
+    /// which can't be found by name with `find_class`
+    pub fn primitive(env: &mut JNIEnv, t: &SpType) -> Result<Self> {
+        let (offset, wrapper) = match t {
+            SpType::Byte => (0, "java/lang/Byte"),
+            SpType::Char => (1, "java/lang/Character"),
+            SpType::Double => (2, "java/lang/Double"),
+            SpType::Float => (3, "java/lang/Float"),
+            SpType::Int => (4, "java/lang/Integer"),
+            SpType::Long => (5, "java/lang/Long"),
+            SpType::Sort => (6, "java/lang/Short"),
+            SpType::Boolean => (7, "java/lang/Boolean"),
+            SpType::Void => (8, "java/lang/Void"),
+            _ => return Err(anyhow!("not a primitive type: {}", t)),
+        };
+        let key = PRIMITIVE_CLASS_START - offset;
+        let class_ref = match CLASS_CACHE.get(&key) {
+            Some(class_ref) => class_ref,
+            None => {
+                trace_miss!("class cache miss: key={}, name={}.TYPE", key, wrapper);
+                let class = env
+                    .get_static_field(wrapper, "TYPE", "Ljava/lang/Class;")?
+                    .l()?;
+                let raw = env.new_global_ref(class)?;
+                cache_class(key, raw.clone());
+                raw
             }
-        } else if self.jni_class_ref.is_some() {
+        };
+        Ok(Self {
+            cache: key,
+            class_full_path: Some(t.to_string()),
+            jni_class_ref: OnceLock::from(class_ref),
+            loaders: Vec::new(),
+        })
+    }
+
+    /// resolve the class and cache it under the key. concurrent `init`s of one key resolve it
+    /// once: the first thread holds the key's init lock across `find_class`, the others wait.
+    ///
+    /// # Deadlock
+    ///
+    /// `find_class` (and `get_*_id` in the member `init`s) runs the `<clinit>` of a class that
+    /// is not initialized yet while the lock is held. when that `<clinit>` is already running
+    /// on another thread and calls `init` on the same key, it waits for the lock while the
+    /// holder waits for the `<clinit>`, and neither goes on. re-entering on the same thread is
+    /// fine. init such keys up front (`preload_classes` in `JNI_OnLoad`) instead of from the
+    /// static initializers of the classes they resolve
+    pub fn init(&mut self, env: &mut JNIEnv) -> Result<()> {
+        if self.jni_class_ref.get().is_some() {
             return Ok(());
         }
+        if self.cache < 0 && self.class_full_path.is_none() {
+            return throw("no class");
+        }
 
         let result = match CLASS_CACHE.get(&self.cache) {
             Some(global_ref) => global_ref,
             None => {
-                let sig = match &self.class_full_path {
-                    Some(name) => name,
-                    None => return throw("no class cache"),
-                };
-                let class = env.find_class(sig)?;
-                let raw = env.new_global_ref(class)?;
-                CLASS_CACHE.insert(self.cache, raw.clone());
-                raw
+                let _guard = init_lock(InitKind::Class, self.cache);
+                match CLASS_CACHE.get(&self.cache) {
+                    Some(global_ref) => global_ref,
+                    None => self.resolve(env)?,
+                }
             }
         };
-        self.jni_class_ref = Some(result);
+        let _ = self.jni_class_ref.set(result);
         Ok(())
     }
 
-    pub fn get_jni_class(&self) -> Result<&JClass> {
-        match &self.jni_class_ref {
-            Some(class_ref) => Ok(<&JClass>::from(class_ref.as_obj())),
-            None => throw("class not init"),
+    fn resolve(&self, env: &mut JNIEnv) -> Result<GlobalRef> {
+        let sig = match &self.class_full_path {
+            Some(name) => name,
+            None => return Err(self.not_found()),
+        };
+        trace_miss!("class cache miss: key={}, name={}", self.cache, sig);
+        let class = if self.loaders.is_empty() {
+            env.find_class(sig)?
+        } else {
+            Self::load_with_loaders(env, sig, &self.loaders)?
+        };
+        let raw = env.new_global_ref(class)?;
+        cache_class(self.cache, raw.clone());
+        Ok(raw)
+    }
+
+    fn load_with_loaders<'local>(
+        env: &mut JNIEnv<'local>,
+        sig: &str,
+        loaders: &[GlobalRef],
+    ) -> Result<JClass<'local>> {
+        let name = env.new_string(sig.replace("/", "."))?;
+        let mut errors = Vec::with_capacity(loaders.len());
+        for (i, loader) in loaders.iter().enumerate() {
+            let result = env
+                .call_method(
+                    loader,
+                    "loadClass",
+                    "(Ljava/lang/String;)Ljava/lang/Class;",
+                    &[JValue::Object(&name)],
+                )
+                .and_then(|v| v.l());
+            match result {
+                Ok(class) => return Ok(JClass::from(class)),
+                Err(e) => {
+                    if env.exception_check()? {
+                        env.exception_clear()?;
+                    }
+                    errors.push(format!("loader[{}]: {}", i, e));
+                }
+            }
+        }
+        Err(anyhow!(
+            "class {} not found in loaders: [{}]",
+            sig,
+            errors.join(", ")
+        ))
+    }
+
+    /// the resolved class of this handle, or of its key in the cache
+    fn class_ref(&self) -> Result<GlobalRef> {
+        if let Some(class_ref) = self.jni_class_ref.get() {
+            return Ok(class_ref.clone());
+        }
+        if self.cache >= 0
+            && let Some(class_ref) = CLASS_CACHE.get(&self.cache)
+        {
+            return Ok(class_ref);
+        }
+        Err(self.not_found())
+    }
+
+    /// `CacheEvicted` only for a nameless handle whose key was cached before,
+    /// a key that was never cached just needs an `init`
+    fn not_found(&self) -> anyhow::Error {
+        if self.cache >= 0 && self.class_full_path.is_none() && was_class_cached(self.cache) {
+            SpError::CacheEvicted(self.cache).into()
+        } else {
+            anyhow!("class not init")
+        }
+    }
+
+    /// java `this.isAssignableFrom(other)`: an `other` can be used where `this` is expected
+    pub fn is_assignable_from(&self, env: &mut JNIEnv, other: &SpClass) -> Result<bool> {
+        let this = self.class_ref()?;
+        let other = other.class_ref()?;
+        // jni `IsAssignableFrom(a, b)` means an `a` can be cast to `b`
+        let result = env.is_assignable_from(
+            <&JClass>::from(other.as_obj()),
+            <&JClass>::from(this.as_obj()),
+        )?;
+        Ok(result)
+    }
+
+    /// `(name, descriptor)` of every field declared by this class through reflection
+    /// (`getDeclaredFields`), nothing is cached, register the ones you need with `SpField`
+    pub fn declared_fields(&self, env: &mut JNIEnv) -> Result<Vec<(String, String)>> {
+        let class_ref = self.class_ref()?;
+        env.with_local_frame(16, |env| {
+            let fields = env
+                .call_method(
+                    class_ref.as_obj(),
+                    "getDeclaredFields",
+                    "()[Ljava/lang/reflect/Field;",
+                    &[],
+                )?
+                .l()?;
+            let fields = JObjectArray::from(fields);
+            let len = env.get_array_length(&fields)?;
+            let mut result = Vec::with_capacity(len as usize);
+            for i in 0..len {
+                let field = env.get_object_array_element(&fields, i)?;
+                let name = JString::from(
+                    env.call_method(&field, "getName", "()Ljava/lang/String;", &[])?
+                        .l()?,
+                );
+                let field_name: String = env.get_string(&name)?.into();
+                let field_type = env
+                    .call_method(&field, "getType", "()Ljava/lang/Class;", &[])?
+                    .l()?;
+                let type_name = JString::from(
+                    env.call_method(&field_type, "getName", "()Ljava/lang/String;", &[])?
+                        .l()?,
+                );
+                let descriptor =
+                    class_name_to_descriptor(&String::from(env.get_string(&type_name)?));
+                result.push((field_name, descriptor));
+                // every field makes four locals, free them so a big class fits in the frame
+                env.delete_local_ref(field)?;
+                env.delete_local_ref(name)?;
+                env.delete_local_ref(field_type)?;
+                env.delete_local_ref(type_name)?;
+            }
+            Ok(result)
+        })
+    }
+
+    /// run the static initializer (`<clinit>`) if it did not run yet, by
+    /// `Class.forName(name, true, loader)`. a class loaded by `ClassLoader.loadClass`
+    /// (`new_with_loaders`) or made by `define` is not initialized until its first active use,
+    /// and the jni spec does not promise it for `find_class` either.
+    /// a primitive class (`SpClass::primitive`) has no initializer, it is `Ok` right away
+    pub fn ensure_initialized(&self, env: &mut JNIEnv) -> Result<()> {
+        let class_ref = self.class_ref()?;
+        env.with_local_frame(4, |env| {
+            if env
+                .call_method(class_ref.as_obj(), "isPrimitive", "()Z", &[])?
+                .z()?
+            {
+                return Ok(());
+            }
+            let name = env
+                .call_method(class_ref.as_obj(), "getName", "()Ljava/lang/String;", &[])?
+                .l()?;
+            let loader = env
+                .call_method(
+                    class_ref.as_obj(),
+                    "getClassLoader",
+                    "()Ljava/lang/ClassLoader;",
+                    &[],
+                )?
+                .l()?;
+            env.call_static_method(
+                "java/lang/Class",
+                "forName",
+                "(Ljava/lang/String;ZLjava/lang/ClassLoader;)Ljava/lang/Class;",
+                &[
+                    JValue::Object(&name),
+                    JValue::Bool(JNI_TRUE),
+                    JValue::Object(&loader),
+                ],
+            )?;
+            Ok(())
+        })
+    }
+
+    /// whether `other` is this class, e.g. `env.get_object_class(obj)` of a received object.
+    /// an exact match only, a subclass is not the same class
+    pub fn is_same_class(&self, env: &mut JNIEnv, other: &JClass) -> Result<bool> {
+        let this = self.class_ref()?;
+        Ok(env.is_same_object(this.as_obj(), other)?)
+    }
+
+    /// `init` if needed, then `get_jni_class`
+    pub fn get_or_init(&mut self, env: &mut JNIEnv) -> Result<&JClass<'_>> {
+        self.init(env)?;
+        self.get_jni_class()
+    }
+
+    /// the `java.lang.Class` object as a plain `JObject`, for reflective apis taking a `Class`
+    /// argument. it is the same reference as `get_jni_class`, only typed as an object
+    pub fn as_class_object(&self) -> Result<&JObject<'static>> {
+        Ok(self.stored_class_ref()?.as_obj())
+    }
+
+    /// the class of this handle, a handle that was not `init`ed finds it in `CLASS_CACHE`
+    /// by its key. fails with `SpError::CacheEvicted` when a `SpClass::cache` handle lost its entry
+    pub fn get_jni_class(&self) -> Result<&JClass<'_>> {
+        Ok(<&JClass>::from(self.stored_class_ref()?.as_obj()))
+    }
+
+    fn stored_class_ref(&self) -> Result<&GlobalRef> {
+        match self.jni_class_ref.get() {
+            Some(class_ref) => Ok(class_ref),
+            None => {
+                let class_ref = self.class_ref()?;
+                Ok(self.jni_class_ref.get_or_init(|| class_ref))
+            }
         }
     }
 }
 
+/// `Class.getName()` -> descriptor: `int` -> `I`, `java.lang.String` -> `Ljava/lang/String;`,
+/// array names (`[Ljava.lang.String;`) are already descriptors with dots
+fn class_name_to_descriptor(name: &str) -> String {
+    match name {
+        "byte" => "B".to_string(),
+        "char" => "C".to_string(),
+        "double" => "D".to_string(),
+        "float" => "F".to_string(),
+        "int" => "I".to_string(),
+        "long" => "J".to_string(),
+        "short" => "S".to_string(),
+        "boolean" => "Z".to_string(),
+        "void" => "V".to_string(),
+        _ if name.starts_with('[') => name.replace('.', "/"),
+        _ => format!("L{};", name.replace('.', "/")),
+    }
+}
+
+/// resolve and cache classes up front (e.g. in `JNI_OnLoad`), stops at the first failure
+pub fn preload_classes(env: &mut JNIEnv, specs: &[(ClassKey, &str)]) -> Result<()> {
+    for (key, sig) in specs {
+        SpClass::new(*key, sig)
+            .init(env)
+            .map_err(|e| e.context(format!("preload class: key={}, sig={}", key, sig)))?;
+    }
+    Ok(())
+}
+
+/// `UnregisterNatives`: drop every native method bound to `class`, by `RegisterNatives` or by
+/// symbol lookup. a plugin system that reloads a class must call it before the old class is
+/// unloaded, so no java call can reach a function pointer of a library that is going away
+pub fn unregister_natives(env: &mut JNIEnv, class: &JClass) -> Result<()> {
+    env.unregister_native_methods(class)?;
+    Ok(())
+}
+
+// every handle only holds a cache key, `String`s and `GlobalRef`s, so handles can move between
+// threads like the global caches they read. a field that breaks this fails to compile here.
+const _: () = {
+    const fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<SpClass>();
+    assert_send_sync::<SpType>();
+    assert_send_sync::<SpStaticField>();
+    assert_send_sync::<SpField>();
+    assert_send_sync::<SpStaticMethod>();
+    assert_send_sync::<SpMethod>();
+};
+
 /// example:
 /// - class: `class: env, key, "java.lang.Object"`
 /// - static_field: `static_field: env, key, &JClass, "name", &SpType`
 /// - field: `field: env, key, &JClass, "name", &SpType`
 /// - static_method: `static_method: env, key, &JClass, "name", &SpType, &[SpType] `
 /// - method: `method: env, key, &JClass, "name", &SpType, &[SpType] `
+///
+/// a key names one class or member for the life of the process, so it should be a constant:
+/// a key computed at runtime can map different members to one cache entry, or fill the cache
+/// with entries that are never read again. `sp_key!(KEY)` fails to compile for a non-const key.
+///
+/// the `*_cached` arms only build the handle from a key that is known to be cached,
+/// they don't touch the jvm and return the handle without `Result`:
+/// - `class_cached: key`, `static_field_cached: key`, `field_cached: key`,
+///   `static_method_cached: key`, `method_cached: key`
 #[macro_export]
 macro_rules! get_sp_struct {
+    (class_cached: $key:expr) => {
+        SpClass::cache($key)
+    };
+    (static_field_cached: $key:expr) => {
+        SpStaticField::cache($key)
+    };
+    (field_cached: $key:expr) => {
+        SpField::cache($key)
+    };
+    (static_method_cached: $key:expr) => {
+        SpStaticMethod::cache($key)
+    };
+    (method_cached: $key:expr) => {
+        SpMethod::cache($key)
+    };
     (class: $env:expr, $key:expr, $sig:expr) => {
         if SpClass::contains_cache($key) {
             let class = SpClass::cache($key);
@@ -422,7 +2047,9 @@ macro_rules! get_sp_struct {
             let mut class = SpClass::new($key, $sig);
             match class.init($env) {
                 Ok(_) => Ok(class),
-                Err(e) => Err(e),
+                Err(e) => {
+                    Err(e.context(format!("get_sp_struct class: key={}, sig={}", $key, $sig)))
+                }
             }
         }
     };
@@ -434,7 +2061,10 @@ macro_rules! get_sp_struct {
             let field = SpStaticField::new($key, $name, $t);
             match field.init($env, $class) {
                 Ok(_) => Ok(field),
-                Err(e) => Err(e),
+                Err(e) => Err(e.context(format!(
+                    "get_sp_struct static_field: key={}, name={}",
+                    $key, $name
+                ))),
             }
         }
     };
@@ -446,7 +2076,9 @@ macro_rules! get_sp_struct {
             let field = SpField::new($key, $name, $t);
             match field.init($env, $class) {
                 Ok(_) => Ok(field),
-                Err(e) => Err(e),
+                Err(e) => {
+                    Err(e.context(format!("get_sp_struct field: key={}, name={}", $key, $name)))
+                }
             }
         }
     };
@@ -458,7 +2090,10 @@ macro_rules! get_sp_struct {
             let method = SpStaticMethod::new($key, $name, $ret, $args);
             match method.init($env, $class) {
                 Ok(_) => Ok(method),
-                Err(e) => Err(e),
+                Err(e) => Err(e.context(format!(
+                    "get_sp_struct static_method: key={}, name={}",
+                    $key, $name
+                ))),
             }
         }
     };
@@ -470,8 +2105,39 @@ macro_rules! get_sp_struct {
             let method = SpMethod::new($key, $name, $ret, $args);
             match method.init($env, $class) {
                 Ok(_) => Ok(method),
-                Err(e) => Err(e),
+                Err(e) => Err(e.context(format!(
+                    "get_sp_struct method: key={}, name={}",
+                    $key, $name
+                ))),
             }
         }
     };
 }
+
+/// a cache key that must be known at compile time: `get_sp_struct!(class: env, sp_key!(1), "...")`
+///
+/// ```
+/// use anyhow::Result;
+/// use jni::JNIEnv;
+/// use jni_sp_util::{SpClass, get_sp_struct, sp_key};
+///
+/// const LIST: i32 = 10;
+///
+/// fn list_class(env: &mut JNIEnv) -> Result<SpClass> {
+///     get_sp_struct!(class: env, sp_key!(LIST), "java/util/ArrayList")
+/// }
+///
+/// assert_eq!(sp_key!(LIST + 1), 11);
+/// ```
+///
+/// ```compile_fail
+/// let key = std::process::id() as i32;
+/// let _ = jni_sp_util::sp_key!(key);
+/// ```
+#[macro_export]
+macro_rules! sp_key {
+    ($key:expr) => {{
+        const KEY: i32 = $key;
+        KEY
+    }};
+}
@@ -1,7 +1,26 @@
+mod array;
+mod convert;
+#[cfg(feature = "diagnostics")]
+mod diagnostics;
 mod error;
+mod java;
 mod jni;
+#[cfg(feature = "metrics")]
+mod metrics;
 mod point;
+mod vm;
 
+pub use array::*;
+pub use convert::*;
+#[cfg(feature = "diagnostics")]
+pub use diagnostics::*;
 pub use error::*;
+pub use java::*;
 pub use jni::*;
+#[cfg(feature = "metrics")]
+pub use metrics::*;
 pub use point::*;
+pub use vm::*;
+
+#[doc(hidden)]
+pub use ::jni as __jni;
@@ -0,0 +1,45 @@
+use std::collections::HashMap;
+use std::sync::{LazyLock, Mutex};
+use std::time::Duration;
+
+use crate::jni::{MethodKey, StaticMethodKey};
+
+#[derive(Clone, Copy, Debug, Default)]
+pub struct CallStats {
+    pub calls: u64,
+    pub total_nanos: u128,
+}
+
+static METHOD_TIMINGS: LazyLock<Mutex<HashMap<MethodKey, CallStats>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+static STATIC_METHOD_TIMINGS: LazyLock<Mutex<HashMap<StaticMethodKey, CallStats>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+fn record(timings: &Mutex<HashMap<i32, CallStats>>, key: i32, elapsed: Duration) {
+    if let Ok(mut timings) = timings.lock() {
+        let stats = timings.entry(key).or_default();
+        stats.calls += 1;
+        stats.total_nanos += elapsed.as_nanos();
+    }
+}
+
+pub(crate) fn record_method(key: MethodKey, elapsed: Duration) {
+    record(&METHOD_TIMINGS, key, elapsed);
+}
+
+pub(crate) fn record_static_method(key: StaticMethodKey, elapsed: Duration) {
+    record(&STATIC_METHOD_TIMINGS, key, elapsed);
+}
+
+/// snapshot of `SpMethod::call` counts and time by key
+pub fn method_timings() -> HashMap<MethodKey, CallStats> {
+    METHOD_TIMINGS.lock().map(|t| t.clone()).unwrap_or_default()
+}
+
+/// snapshot of `SpStaticMethod::call` counts and time by key
+pub fn static_method_timings() -> HashMap<StaticMethodKey, CallStats> {
+    STATIC_METHOD_TIMINGS
+        .lock()
+        .map(|t| t.clone())
+        .unwrap_or_default()
+}
@@ -1,6 +1,16 @@
+use crate::convert::FromJValue;
 use crate::error::{Result, anyhow, throw};
-use jni::sys::jlong;
+use crate::jni::SpField;
+use jni::{
+    JNIEnv,
+    objects::{JByteBuffer, JObject, JValue},
+    signature::{Primitive, ReturnType},
+    sys::jlong,
+};
 use replace_with::replace_with_or_abort;
+use std::any::{Any, TypeId, type_name};
+use std::collections::HashMap;
+use std::sync::{LazyLock, Mutex, MutexGuard, PoisonError};
 
 pub type Point = usize;
 
@@ -19,16 +29,50 @@ impl ToJavaPoint for Point {
     }
 }
 
+/// every pointer made by `to_ptr` and not yet taken back by `to_status`,
+/// so a second `to_status` of one pointer is an error instead of a double free.
+/// pointers of `to_ptr_checked` also keep their `TypeId` here, never in the allocation,
+/// so checking the type of a freed or forged pointer reads no memory through it
+static LIVE_POINTS: LazyLock<Mutex<HashMap<Point, Option<TypeId>>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+fn live_points() -> MutexGuard<'static, HashMap<Point, Option<TypeId>>> {
+    LIVE_POINTS.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+fn unregister(p: Point) -> Result<()> {
+    if live_points().remove(&p).is_none() {
+        return Err(anyhow!("pointer already freed or not from to_ptr: ({})", p));
+    }
+    Ok(())
+}
+
+/// a zero sized `T` (a unit struct marker) is not allocated: every pointer of it is the same
+/// dangling, aligned address. such pointers are not tracked in `LIVE_POINTS`, so `to_status`
+/// of them never fails as freed, which is fine since there is nothing to free
 #[inline]
 pub fn to_ptr<T>(s: T) -> Point {
-    Box::into_raw(Box::new(s)) as Point
+    let p = Box::into_raw(Box::new(s)) as Point;
+    if size_of::<T>() != 0 {
+        live_points().insert(p, None);
+    }
+    p
 }
 
+/// a forged or corrupted pointer can also be misaligned for `T` (think `#[repr(align(64))]`),
+/// reading through it would be undefined behavior, so it is an error like `null`
 #[inline]
 fn check_ptr<T>(point: *mut T) -> Result<()> {
     if point.is_null() {
         return throw("point is null or not");
     }
+    if !point.is_aligned() {
+        return Err(anyhow!(
+            "point is not aligned for {}: ({})",
+            type_name::<T>(),
+            point as usize
+        ));
+    }
     Ok(())
 }
 
@@ -43,6 +87,11 @@ pub fn to_status_use<T>(p: Point) -> Result<&'static mut T> {
     }
 }
 
+/// replace the value with `action(value)`.
+/// a panic in `action` aborts the process with either panic strategy: the value is moved out
+/// while `action` runs and `replace_with_or_abort` has nothing to put back, so the
+/// `catch_unwind` here never returns an error for it (and under `cfg(panic = "abort")`
+/// nothing unwinds at all)
 #[inline]
 pub fn to_status_replace<T>(p: Point, action: impl FnOnce(T) -> T) -> Result<()> {
     use std::panic::{AssertUnwindSafe, catch_unwind};
@@ -62,15 +111,211 @@ pub fn to_status_replace<T>(p: Point, action: impl FnOnce(T) -> T) -> Result<()>
     }
 }
 
+/// mutate the value in place and return something computed from it,
+/// `to_status_replace` + `to_status_use` in one lookup
+#[inline]
+pub fn to_status_map<T, R>(p: Point, action: impl FnOnce(&mut T) -> R) -> Result<R> {
+    let point = p as *mut T;
+    check_ptr(point)?;
+    let status_use = unsafe {
+        point
+            .as_mut()
+            .ok_or_else(|| anyhow!("read pointer error: ({})", p))
+    }?;
+    Ok(action(status_use))
+}
+
 #[inline]
 pub fn to_status<T>(p: Point) -> Result<Box<T>> {
     let point = p as *mut T;
     check_ptr(point)?;
+    if size_of::<T>() != 0 {
+        unregister(p)?;
+    }
     unsafe {
-        if let None = point.as_ref() {
+        if point.as_ref().is_none() {
             Err(anyhow!("read pointer error: ({})", p))
         } else {
             Ok(Box::from_raw(point))
         }
     }
 }
+
+/// drop the value of a `to_ptr` pointer, freeing it twice is an error
+#[inline]
+pub fn free_ptr<T>(p: Point) -> Result<()> {
+    drop(to_status::<T>(p)?);
+    Ok(())
+}
+
+/// like `to_ptr`, but the registry remembers `T`,
+/// read it back with `to_status_use_checked` / `to_status_checked`
+#[inline]
+pub fn to_ptr_checked<T: 'static>(s: T) -> Point {
+    let p = Box::into_raw(Box::new(s)) as Point;
+    if size_of::<T>() != 0 {
+        live_points().insert(p, Some(TypeId::of::<T>()));
+    }
+    p
+}
+
+/// the pointer of a live `to_ptr_checked` value of type `T`, judged from `LIVE_POINTS` only.
+/// a zero sized `T` is not tracked (see `to_ptr`), any aligned pointer is fine for it
+fn check_type<T: 'static>(p: Point) -> Result<*mut T> {
+    let point = p as *mut T;
+    check_ptr(point)?;
+    if size_of::<T>() == 0 {
+        return Ok(point);
+    }
+    match live_points().get(&p) {
+        None => Err(anyhow!("pointer already freed or not from to_ptr: ({})", p)),
+        Some(Some(type_id)) if *type_id == TypeId::of::<T>() => Ok(point),
+        Some(Some(_)) => Err(anyhow!(
+            "pointer type mismatch: ({}) is not {}",
+            p,
+            type_name::<T>()
+        )),
+        Some(None) => Err(anyhow!("pointer not from to_ptr_checked: ({})", p)),
+    }
+}
+
+#[inline]
+pub fn to_status_use_checked<T: 'static>(p: Point) -> Result<&'static mut T> {
+    let point = check_type::<T>(p)?;
+    unsafe { Ok(&mut *point) }
+}
+
+/// take back the value of a `to_ptr_checked` pointer,
+/// the allocation is not touched if `T` is not the type it was created with
+#[inline]
+pub fn to_status_checked<T: 'static>(p: Point) -> Result<T> {
+    let point = check_type::<T>(p)?;
+    if size_of::<T>() != 0 {
+        unregister(p)?;
+    }
+    let value = unsafe { Box::from_raw(point) };
+    Ok(*value)
+}
+
+/// keep `ptr` in the java `long` field `field` of `obj`, like a `nativeHandle` field
+pub fn attach_handle(env: &mut JNIEnv, obj: &JObject, field: &SpField, ptr: Point) -> Result<()> {
+    field.set(env, obj, JValue::Long(ptr.to_point()))
+}
+
+/// read back the pointer of `attach_handle`, same as `to_status_use`
+pub fn read_handle<T>(env: &mut JNIEnv, obj: &JObject, field: &SpField) -> Result<&'static mut T> {
+    let value = field.call(env, obj, ReturnType::Primitive(Primitive::Long))?;
+    let p = Point::from_java_long(i64::from_jvalue(value)?);
+    to_status_use(p)
+}
+
+struct HandleSlot {
+    generation: u32,
+    value: Option<Box<dyn Any + Send>>,
+}
+
+#[derive(Default)]
+struct HandleTable {
+    slots: Vec<HandleSlot>,
+    free: Vec<usize>,
+}
+
+/// values of `register_handle`, java only sees `generation << 32 | index`, never an address
+static HANDLES: LazyLock<Mutex<HandleTable>> = LazyLock::new(Default::default);
+
+fn handles() -> MutexGuard<'static, HandleTable> {
+    HANDLES.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// the slot index of a live handle, a forged or released handle is an error
+fn check_handle(table: &HandleTable, id: jlong) -> Result<usize> {
+    let index = (id as u64 & 0xffff_ffff) as usize;
+    let generation = (id as u64 >> 32) as u32;
+    match table.slots.get(index) {
+        None => Err(anyhow!("handle out of range: ({})", id)),
+        Some(slot) if slot.generation != generation || slot.value.is_none() => {
+            Err(anyhow!("stale handle: ({})", id))
+        }
+        Some(_) => Ok(index),
+    }
+}
+
+/// like `to_ptr`, but java gets an opaque id instead of a heap address.
+/// read it with `resolve_handle`, take it back with `release_handle`
+pub fn register_handle<T: Send + 'static>(s: T) -> jlong {
+    let mut table = handles();
+    let value = Some(Box::new(s) as Box<dyn Any + Send>);
+    let index = match table.free.pop() {
+        Some(index) => {
+            table.slots[index].value = value;
+            index
+        }
+        None => {
+            table.slots.push(HandleSlot {
+                generation: 1,
+                value,
+            });
+            table.slots.len() - 1
+        }
+    };
+    ((table.slots[index].generation as u64) << 32 | index as u64) as jlong
+}
+
+/// same as `to_status_use` for a `register_handle` id, a forged or stale id is an error.
+///
+/// # Safety
+/// the returned reference is not tied to the table. it must not be used after
+/// `release_handle` of `id` drops the value, and there must be no other live reference from
+/// `resolve_handle` of the same id, e.g. from two threads calling into the same java object
+pub unsafe fn resolve_handle<T: 'static>(id: jlong) -> Result<&'static mut T> {
+    let mut table = handles();
+    let index = check_handle(&table, id)?;
+    let value = table.slots[index]
+        .value
+        .as_mut()
+        .and_then(|value| value.downcast_mut::<T>())
+        .ok_or_else(|| anyhow!("handle type mismatch: ({}) is not {}", id, type_name::<T>()))?;
+    // the boxed value does not move until `release_handle` takes it out of the slot
+    Ok(unsafe { &mut *(value as *mut T) })
+}
+
+/// take back the value of a `register_handle` id, the id is stale afterwards
+pub fn release_handle<T: 'static>(id: jlong) -> Result<T> {
+    let mut table = handles();
+    let index = check_handle(&table, id)?;
+    let slot = &mut table.slots[index];
+    let value = slot
+        .value
+        .take()
+        .ok_or_else(|| anyhow!("stale handle: ({})", id))?;
+    match value.downcast::<T>() {
+        Ok(value) => {
+            slot.generation = slot.generation.wrapping_add(1).max(1);
+            table.free.push(index);
+            Ok(*value)
+        }
+        Err(value) => {
+            slot.value = Some(value);
+            Err(anyhow!(
+                "handle type mismatch: ({}) is not {}",
+                id,
+                type_name::<T>()
+            ))
+        }
+    }
+}
+
+/// a java `DirectByteBuffer` reading and writing `data` in place, no copy.
+/// keep the bytes in a `to_ptr::<Vec<u8>>` point (`to_status_use::<Vec<u8>>(p)?`) so they stay
+/// put, and free the point only when java is done with the buffer
+///
+/// # Safety
+/// `data` must not move, shrink or be freed while java can reach the buffer,
+/// which may outlive the native call that made it
+pub unsafe fn expose_as_direct_buffer<'local>(
+    env: &mut JNIEnv<'local>,
+    data: &mut [u8],
+) -> Result<JByteBuffer<'local>> {
+    let buffer = unsafe { env.new_direct_byte_buffer(data.as_mut_ptr(), data.len())? };
+    Ok(buffer)
+}
@@ -0,0 +1,235 @@
+use jni::{
+    JNIEnv, JavaVM,
+    objects::{GlobalRef, JObject, JThrowable, JValue},
+    sys::JNI_VERSION_1_6,
+};
+use std::sync::{OnceLock, mpsc};
+use std::thread::JoinHandle;
+
+use crate::error::{Result, SpError, anyhow};
+use crate::java::bi_consumer_accept;
+
+static JAVA_VM: OnceLock<JavaVM> = OnceLock::new();
+
+/// store the `JavaVM`, usually in `JNI_OnLoad`. only the first call takes effect
+pub fn set_java_vm(vm: JavaVM) {
+    let _ = JAVA_VM.set(vm);
+}
+
+pub fn get_java_vm() -> Result<&'static JavaVM> {
+    JAVA_VM
+        .get()
+        .ok_or_else(|| SpError::AttachFailed("java vm not set".to_string()).into())
+}
+
+/// run `action` with the `JNIEnv` of the current thread, attach the thread if it is not attached,
+/// failing to get the env at all is `SpError::AttachFailed`, errors of `action` are passed through
+pub fn with_env<R>(action: impl FnOnce(&mut JNIEnv) -> Result<R>) -> Result<R> {
+    let vm = get_java_vm()?;
+    let mut env = vm
+        .attach_current_thread()
+        .map_err(|e| SpError::AttachFailed(e.to_string()))?;
+    action(&mut env)
+}
+
+/// the jni version of the jvm, like `0x00010008` (`JNI_VERSION_1_8`) or `0x00150000` for java 21.
+///
+/// every helper of this crate works with jni 1.6, which all supported jvms provide
+pub fn jni_version(env: &JNIEnv) -> Result<i32> {
+    Ok(env.get_version()?.into())
+}
+
+/// what kind of reference a `JObject` is, see `ref_type`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RefKind {
+    Local,
+    Global,
+    WeakGlobal,
+    /// deleted, never valid, or `null`
+    Invalid,
+}
+
+/// `GetObjectRefType` of `obj`, for finding references used after they were deleted.
+/// needs jni 1.6
+pub fn ref_type(env: &JNIEnv, obj: &JObject) -> Result<RefKind> {
+    let version = jni_version(env)?;
+    if version < JNI_VERSION_1_6 {
+        return Err(anyhow!(
+            "GetObjectRefType needs jni 1.6, the jvm has {:#x}",
+            version
+        ));
+    }
+    let raw = env.get_raw();
+    let get_object_ref_type = unsafe { (**raw).GetObjectRefType }
+        .ok_or_else(|| anyhow!("GetObjectRefType is missing from the jni function table"))?;
+    let kind = unsafe { get_object_ref_type(raw, obj.as_raw()) };
+    Ok(match kind as i32 {
+        1 => RefKind::Local,
+        2 => RefKind::Global,
+        3 => RefKind::WeakGlobal,
+        _ => RefKind::Invalid,
+    })
+}
+
+/// run `action` on a new attached thread, then report to the `java.util.function.BiConsumer`
+/// `callback`: `accept(result, null)` on success, `accept(null, throwable)` on error.
+///
+/// if no `JavaVM` was stored yet, the one of `env` is stored.
+pub fn spawn_with_callback<F>(
+    env: &mut JNIEnv,
+    callback: GlobalRef,
+    action: F,
+) -> Result<JoinHandle<Result<()>>>
+where
+    F: for<'local> FnOnce(&mut JNIEnv<'local>) -> Result<JObject<'local>> + Send + 'static,
+{
+    if JAVA_VM.get().is_none() {
+        set_java_vm(env.get_java_vm()?);
+    }
+    let handle = std::thread::spawn(move || {
+        with_env(|env| {
+            let null = JObject::null();
+            let result = match action(env) {
+                Ok(value) => bi_consumer_accept(env, callback.as_obj(), &value, &null),
+                Err(e) => {
+                    let throwable = error_to_throwable(env, e)?;
+                    bi_consumer_accept(env, callback.as_obj(), &null, &throwable)
+                }
+            };
+            if env.exception_check()? {
+                env.exception_describe()?;
+                env.exception_clear()?;
+            }
+            result
+        })
+    });
+    Ok(handle)
+}
+
+/// take the pending exception if there is one, otherwise wrap the message in a `RuntimeException`
+fn error_to_throwable<'local>(
+    env: &mut JNIEnv<'local>,
+    error: anyhow::Error,
+) -> Result<JThrowable<'local>> {
+    if env.exception_check()? {
+        let throwable = env.exception_occurred()?;
+        env.exception_clear()?;
+        return Ok(throwable);
+    }
+    let message = env.new_string(format!("{:?}", error))?;
+    let throwable = env.new_object(
+        "java/lang/RuntimeException",
+        "(Ljava/lang/String;)V",
+        &[JValue::Object(&message)],
+    )?;
+    Ok(JThrowable::from(throwable))
+}
+
+type Task = Box<dyn for<'local> FnOnce(&mut JNIEnv<'local>) + Send>;
+
+/// one thread attached to the stored `JavaVM` for good, running submitted tasks in order.
+/// useful to keep all jni access on one thread without attaching for every call
+pub struct JniExecutor {
+    sender: Option<mpsc::Sender<Task>>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl JniExecutor {
+    pub fn new() -> Result<Self> {
+        let vm = get_java_vm()?;
+        let (sender, receiver) = mpsc::channel::<Task>();
+        let (ready_sender, ready) = mpsc::channel();
+        let thread = std::thread::spawn(move || {
+            let mut env = match vm.attach_current_thread_permanently() {
+                Ok(env) => {
+                    let _ = ready_sender.send(Ok(()));
+                    env
+                }
+                Err(e) => {
+                    let _ = ready_sender.send(Err(SpError::AttachFailed(e.to_string())));
+                    return;
+                }
+            };
+            for task in receiver {
+                // the thread never returns to java, so free the local refs of every task
+                let _ = env.with_local_frame(16, |env| -> Result<()> {
+                    task(env);
+                    if env.exception_check()? {
+                        env.exception_describe()?;
+                        env.exception_clear()?;
+                    }
+                    Ok(())
+                });
+            }
+        });
+        ready
+            .recv()
+            .map_err(|_| anyhow!("jni executor thread stopped"))??;
+        Ok(Self {
+            sender: Some(sender),
+            thread: Some(thread),
+        })
+    }
+
+    /// run `task` on the executor thread, the result arrives on the returned receiver.
+    /// local refs made by `task` are deleted when it returns, keep objects as `GlobalRef`
+    pub fn submit<R, F>(&self, task: F) -> Result<mpsc::Receiver<Result<R>>>
+    where
+        R: Send + 'static,
+        F: for<'local> FnOnce(&mut JNIEnv<'local>) -> Result<R> + Send + 'static,
+    {
+        let (result_sender, result) = mpsc::channel();
+        let task: Task = Box::new(move |env| {
+            let _ = result_sender.send(task(env));
+        });
+        self.sender
+            .as_ref()
+            .ok_or_else(|| anyhow!("jni executor stopped"))?
+            .send(task)
+            .map_err(|_| anyhow!("jni executor stopped"))?;
+        Ok(result)
+    }
+}
+
+impl Drop for JniExecutor {
+    fn drop(&mut self) {
+        // closing the channel ends the thread after the queued tasks
+        self.sender.take();
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+/// generate the `JNI_OnLoad` of the library: store the `JavaVM` with `set_java_vm`, then
+/// `preload_classes` the given `(key, name)` pairs. it returns `JNI_VERSION_1_6`, or `JNI_ERR`
+/// (`System.loadLibrary` fails) on an error or a panic, which never crosses into the jvm.
+/// the fn is `unsafe`, it reads the raw `JavaVM` pointer it is given.
+/// that needs `panic = "unwind"`, the default: in a library built with `panic = "abort"`
+/// (`cfg(panic = "abort")`) a panic while loading aborts the jvm instead.
+///
+/// `jni_on_load!();` or `jni_on_load! { preload: [(KEY, "java/lang/String")] }`
+#[macro_export]
+macro_rules! jni_on_load {
+    ($(preload: [$(($key:expr, $name:expr)),* $(,)?])? $(,)?) => {
+        #[unsafe(no_mangle)]
+        pub unsafe extern "system" fn JNI_OnLoad(
+            vm: *mut $crate::__jni::sys::JavaVM,
+            _reserved: *mut ::std::ffi::c_void,
+        ) -> $crate::__jni::sys::jint {
+            let result = ::std::panic::catch_unwind(::std::panic::AssertUnwindSafe(
+                || -> $crate::Result<()> {
+                    let vm = unsafe { $crate::__jni::JavaVM::from_raw(vm) }?;
+                    $crate::set_java_vm(vm);
+                    let mut env = $crate::get_java_vm()?.get_env()?;
+                    $crate::preload_classes(&mut env, &[$($(($key, $name)),*)?])?;
+                    Ok(())
+                },
+            ));
+            match result {
+                Ok(Ok(())) => $crate::__jni::sys::JNI_VERSION_1_6,
+                _ => $crate::__jni::sys::JNI_ERR,
+            }
+        }
+    };
+}
@@ -0,0 +1,173 @@
+mod common;
+
+use jni::JNIEnv;
+use jni::objects::{JByteBuffer, JIntArray, JValue};
+use jni_sp_util::{
+    SpStaticMethod, SpType, WriteBack, jboolean_array_to_vec, jbyte_array_into_point,
+    long_array_arg, pin_array, pin_direct_buffer, point_into_jbyte_array, to_status_use,
+    vec_to_jboolean_array, write_int_array,
+};
+
+fn ints(env: &mut JNIEnv, array: &JIntArray) -> [i32; 3] {
+    let mut values = [0; 3];
+    env.get_int_array_region(array, 0, &mut values).unwrap();
+    values
+}
+
+fn direct_buffer<'local>(env: &mut JNIEnv<'local>) -> JByteBuffer<'local> {
+    let buffer = env
+        .call_static_method(
+            "java/nio/ByteBuffer",
+            "allocateDirect",
+            "(I)Ljava/nio/ByteBuffer;",
+            &[3.into()],
+        )
+        .unwrap()
+        .l()
+        .unwrap();
+    JByteBuffer::from(buffer)
+}
+
+fn java_byte(env: &mut JNIEnv, buffer: &JByteBuffer, index: i32) -> i8 {
+    env.call_method(buffer, "get", "(I)B", &[index.into()])
+        .unwrap()
+        .b()
+        .unwrap()
+}
+
+#[test]
+fn array_guard_releases_at_scope_exit() {
+    common::with_jvm(|env| {
+        let array = env.new_int_array(3).unwrap();
+        {
+            let mut guard = unsafe { pin_array(env, &array, WriteBack::Commit) }.unwrap();
+            guard[1] = 5;
+        }
+        assert_eq!(ints(env, &array), [0, 5, 0]);
+    });
+}
+
+#[test]
+fn array_guard_commit_and_abort_differ() {
+    common::with_jvm(|env| {
+        let array = env.new_int_array(3).unwrap();
+        {
+            let mut guard = unsafe { pin_array(env, &array, WriteBack::Abort) }.unwrap();
+            guard[0] = 7;
+        }
+        assert_eq!(ints(env, &array), [0, 0, 0]);
+
+        let mut guard = unsafe { pin_array(env, &array, WriteBack::Commit) }.unwrap();
+        guard[0] = 7;
+        guard.abort();
+        assert_eq!(ints(env, &array), [0, 0, 0]);
+
+        let mut guard = unsafe { pin_array(env, &array, WriteBack::Abort) }.unwrap();
+        guard[2] = 9;
+        guard.commit().unwrap();
+        drop(guard);
+        assert_eq!(ints(env, &array), [0, 0, 9]);
+    });
+}
+
+#[test]
+fn direct_buffer_guard_releases_at_scope_exit() {
+    common::with_jvm(|env| {
+        let buffer = direct_buffer(env);
+        {
+            let mut guard = unsafe { pin_direct_buffer(env, &buffer, WriteBack::Commit) }.unwrap();
+            assert_eq!(guard.len(), 3);
+            guard[1] = 5;
+            assert_eq!(java_byte(env, &buffer, 1), 0);
+        }
+        assert_eq!(java_byte(env, &buffer, 1), 5);
+    });
+}
+
+#[test]
+fn direct_buffer_guard_commit_and_abort_differ() {
+    common::with_jvm(|env| {
+        let buffer = direct_buffer(env);
+        {
+            let mut guard = unsafe { pin_direct_buffer(env, &buffer, WriteBack::Abort) }.unwrap();
+            guard[0] = 7;
+        }
+        assert_eq!(java_byte(env, &buffer, 0), 0);
+
+        let mut guard = unsafe { pin_direct_buffer(env, &buffer, WriteBack::Commit) }.unwrap();
+        guard[0] = 7;
+        guard.abort();
+        assert_eq!(java_byte(env, &buffer, 0), 0);
+
+        let mut guard = unsafe { pin_direct_buffer(env, &buffer, WriteBack::Abort) }.unwrap();
+        guard[2] = 9;
+        guard.commit();
+        drop(guard);
+        assert_eq!(java_byte(env, &buffer, 2), 9);
+    });
+}
+
+#[test]
+fn byte_array_round_trip_through_a_point() {
+    common::with_jvm(|env| {
+        let array = env.byte_array_from_slice(&[1, 2, 250]).unwrap();
+        let p = jbyte_array_into_point(env, &array).unwrap();
+        to_status_use::<Box<[u8]>>(p).unwrap()[0] = 9;
+
+        let back = point_into_jbyte_array(env, p).unwrap();
+        assert_eq!(env.convert_byte_array(&back).unwrap(), [9, 2, 250]);
+        assert!(point_into_jbyte_array(env, p).is_err());
+    });
+}
+
+#[test]
+fn write_int_array_fills_an_out_parameter() {
+    common::with_jvm(|env| {
+        let array = env.new_int_array(3).unwrap();
+        write_int_array(env, &array, 0, &[4, 5, 6]).unwrap();
+        assert_eq!(ints(env, &array), [4, 5, 6]);
+
+        assert!(write_int_array(env, &array, 1, &[7, 8, 9]).is_err());
+        assert!(write_int_array(env, &array, 4, &[]).is_err());
+        assert!(!env.exception_check().unwrap());
+        assert_eq!(ints(env, &array), [4, 5, 6]);
+    });
+}
+
+#[test]
+fn long_array_arg_is_passed_to_a_long_array_method() {
+    common::with_jvm(|env| {
+        let class = env.find_class("java/util/Arrays").unwrap();
+        let to_string = SpStaticMethod::new(
+            4901,
+            "toString",
+            &SpType::new_class("java/lang/String"),
+            &[SpType::array_of(SpType::Long, 1)],
+        );
+        to_string.init(env, &class).unwrap();
+
+        let values = long_array_arg(env, &[i64::MIN, 0, i64::MAX]).unwrap();
+        let text = to_string
+            .call_string(env, &class, &[JValue::Object(&values).as_jni()])
+            .unwrap();
+        assert_eq!(
+            text.as_deref(),
+            Some("[-9223372036854775808, 0, 9223372036854775807]")
+        );
+    });
+}
+
+#[test]
+fn boolean_array_round_trips() {
+    common::with_jvm(|env| {
+        let array = vec_to_jboolean_array(env, &[true, false, true]).unwrap();
+        assert_eq!(
+            jboolean_array_to_vec(env, &array).unwrap(),
+            [true, false, true]
+        );
+
+        // a nonstandard nonzero element still reads as true
+        env.set_boolean_array_region(&array, 1, &[2]).unwrap();
+        assert_eq!(jboolean_array_to_vec(env, &array).unwrap(), [true; 3]);
+    });
+}
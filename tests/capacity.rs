@@ -0,0 +1,50 @@
+mod common;
+
+use jni_sp_util::{CacheConfig, SpClass, SpType, debug_to_string, init_caches, option_to_optional};
+
+const CLASSES: [&str; 8] = [
+    "java/util/ArrayList",
+    "java/util/LinkedList",
+    "java/util/HashMap",
+    "java/util/TreeMap",
+    "java/util/HashSet",
+    "java/util/TreeSet",
+    "java/util/ArrayDeque",
+    "java/util/PriorityQueue",
+];
+
+/// the helper classes of the crate do not count against `class_capacity`,
+/// all `class_capacity` classes of the user stay resident next to them
+#[test]
+fn class_capacity_classes_stay_resident_next_to_the_reserved_ones() {
+    init_caches(CacheConfig {
+        class_capacity: CLASSES.len() as u64,
+        ..CacheConfig::default()
+    })
+    .unwrap();
+    common::with_jvm(|env| {
+        for (key, name) in (4501..).zip(CLASSES) {
+            SpClass::new(key, name).init(env).unwrap();
+        }
+        let primitives = [
+            SpType::Byte,
+            SpType::Char,
+            SpType::Double,
+            SpType::Float,
+            SpType::Int,
+            SpType::Long,
+            SpType::Sort,
+            SpType::Boolean,
+            SpType::Void,
+        ];
+        for t in &primitives {
+            SpClass::primitive(env, t).unwrap();
+        }
+        let empty = option_to_optional(env, None).unwrap();
+        debug_to_string(env, &empty).unwrap();
+
+        for (key, name) in (4501..).zip(CLASSES) {
+            assert!(SpClass::contains_cache(key), "{} was evicted", name);
+        }
+    });
+}
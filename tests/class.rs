@@ -0,0 +1,347 @@
+mod common;
+
+use jni::JNIEnv;
+use jni::objects::{GlobalRef, JObject, JValue};
+use jni_sp_util::{
+    CLASS_CACHE, SpClass, SpError, SpStaticMethod, SpType, get_sp_struct, preload_classes,
+};
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+
+fn hash_of(class: &SpClass) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    class.hash(&mut hasher);
+    hasher.finish()
+}
+
+#[test]
+fn dotted_and_slashed_names_are_equal() {
+    let dotted = SpClass::new(1, "java.lang.String");
+    let slashed = SpClass::new(2, "java/lang/String");
+    assert!(dotted == slashed);
+    assert_eq!(hash_of(&dotted), hash_of(&slashed));
+}
+
+#[test]
+fn different_names_are_not_equal() {
+    assert!(SpClass::new(1, "java/lang/String") != SpClass::new(1, "java/lang/Object"));
+}
+
+#[test]
+fn cache_only_handles_compare_by_key() {
+    assert!(SpClass::cache(1) == SpClass::cache(1));
+    assert!(SpClass::cache(1) != SpClass::cache(2));
+    assert_eq!(hash_of(&SpClass::cache(1)), hash_of(&SpClass::cache(1)));
+    assert!(SpClass::cache(1) != SpClass::new(1, "java/lang/String"));
+}
+
+#[test]
+fn class_cached_handle_is_usable_without_init() {
+    common::with_jvm(|env| {
+        let class = get_sp_struct!(class: env, 4101, "java/lang/StringBuilder").unwrap();
+        assert!(class.get_jni_class().is_ok());
+
+        let cached = get_sp_struct!(class_cached: 4101);
+        let jni_class = cached.get_jni_class().unwrap();
+        assert!(
+            env.is_same_object(jni_class, class.get_jni_class().unwrap())
+                .unwrap()
+        );
+        assert!(get_sp_struct!(class_cached: 4102).get_jni_class().is_err());
+    });
+}
+
+#[test]
+fn evicted_class_reports_cache_evicted() {
+    common::with_jvm(|env| {
+        let mut class = SpClass::new(4111, "java/lang/StringBuffer");
+        class.init(env).unwrap();
+        let held = SpClass::cache(4111);
+        CLASS_CACHE.invalidate(&4111);
+
+        assert!(held.get_jni_class().is_ok());
+        let err = SpClass::cache(4111).get_jni_class().err().unwrap();
+        assert!(matches!(
+            err.downcast_ref::<SpError>(),
+            Some(SpError::CacheEvicted(4111))
+        ));
+
+        let mut named = SpClass::new(4111, "java/lang/StringBuffer");
+        named.init(env).unwrap();
+        assert!(SpClass::cache(4111).get_jni_class().is_ok());
+    });
+}
+
+#[test]
+fn never_cached_key_is_not_evicted() {
+    let err = SpClass::cache(4112).get_jni_class().err().unwrap();
+    assert!(err.downcast_ref::<SpError>().is_none());
+}
+
+#[test]
+fn declared_fields_lists_name_and_descriptor() {
+    common::with_jvm(|env| {
+        let mut class = SpClass::new(4121, "java/lang/Integer");
+        class.init(env).unwrap();
+        let fields = class.declared_fields(env).unwrap();
+        let has =
+            |name: &str, descriptor: &str| fields.iter().any(|(n, d)| n == name && d == descriptor);
+        assert!(has("value", "I"));
+        assert!(has("TYPE", "Ljava/lang/Class;"));
+        assert!(has("digits", "[C"));
+    });
+}
+
+/// a `URLClassLoader` over `dirs` with the boot loader as parent
+fn url_loader(env: &mut JNIEnv, dirs: &[&str]) -> GlobalRef {
+    let urls = env
+        .new_object_array(dirs.len() as i32, "java/net/URL", JObject::null())
+        .unwrap();
+    for (i, dir) in dirs.iter().enumerate() {
+        let path = env.new_string(dir).unwrap();
+        let file = env
+            .new_object("java/io/File", "(Ljava/lang/String;)V", &[(&path).into()])
+            .unwrap();
+        let uri = env
+            .call_method(&file, "toURI", "()Ljava/net/URI;", &[])
+            .unwrap()
+            .l()
+            .unwrap();
+        let url = env
+            .call_method(&uri, "toURL", "()Ljava/net/URL;", &[])
+            .unwrap()
+            .l()
+            .unwrap();
+        env.set_object_array_element(&urls, i as i32, url).unwrap();
+    }
+    let loader = env
+        .new_object(
+            "java/net/URLClassLoader",
+            "([Ljava/net/URL;Ljava/lang/ClassLoader;)V",
+            &[(&urls).into(), (&JObject::null()).into()],
+        )
+        .unwrap();
+    env.new_global_ref(loader).unwrap()
+}
+
+#[test]
+fn loaders_are_tried_in_order() {
+    common::with_jvm(|env| {
+        let java_dir = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/java/");
+        let empty = url_loader(env, &[]);
+        let tests = url_loader(env, &[java_dir]);
+
+        let mut class = SpClass::new_with_loaders(4601, "Loaded", &[empty.clone(), tests]);
+        class.init(env).unwrap();
+        let jclass = class.get_jni_class().unwrap();
+        let answer = env
+            .call_static_method(jclass, "answer", "()I", &[])
+            .unwrap();
+        assert_eq!(answer.i().unwrap(), 42);
+
+        let mut missing = SpClass::new_with_loaders(4602, "Loaded", &[empty.clone(), empty]);
+        let err = missing.init(env).err().unwrap().to_string();
+        assert!(err.starts_with("class Loaded not found in loaders: [loader[0]: "));
+        assert!(err.contains(", loader[1]: "));
+        assert!(!SpClass::contains_cache(4602));
+    });
+}
+
+#[test]
+fn define_loads_precompiled_bytecode() {
+    common::with_jvm(|env| {
+        let loader = url_loader(env, &[]);
+        let bytes = common::class_bytes("Defined");
+        let class = SpClass::define(env, 4611, "Defined", &loader, &bytes).unwrap();
+        assert!(SpClass::contains_cache(4611));
+
+        let jclass = class.get_jni_class().unwrap();
+        let greet = SpStaticMethod::new(
+            4611,
+            "greet",
+            &SpType::new_class("java/lang/String"),
+            &[SpType::new_class("java/lang/String")],
+        );
+        greet.init(env, jclass).unwrap();
+        let name = env.new_string("rust").unwrap();
+        let value = greet
+            .call_string(env, jclass, &[JValue::Object(&name).as_jni()])
+            .unwrap();
+        assert_eq!(value.as_deref(), Some("hello rust"));
+
+        let err = SpClass::define(env, 4612, "Empty", &loader, &[])
+            .err()
+            .unwrap();
+        assert_eq!(err.to_string(), "define class Empty: empty class bytes");
+        assert!(!SpClass::contains_cache(4612));
+    });
+}
+
+#[test]
+fn get_or_init_on_fresh_and_initialized_handles() {
+    common::with_jvm(|env| {
+        let expected = env.find_class("java/lang/StringBuilder").unwrap();
+        let mut class = SpClass::new(4621, "java/lang/StringBuilder");
+        assert!(!SpClass::contains_cache(4621));
+        let first = class.get_or_init(env).unwrap().as_raw();
+        assert!(SpClass::contains_cache(4621));
+        let second = class.get_or_init(env).unwrap().as_raw();
+        assert_eq!(first, second);
+        let jclass = class.get_jni_class().unwrap();
+        assert!(env.is_same_object(jclass, &expected).unwrap());
+    });
+}
+
+#[test]
+fn is_assignable_from_follows_java_order() {
+    common::with_jvm(|env| {
+        let mut number = SpClass::new(4631, "java/lang/Number");
+        let mut integer = SpClass::new(4632, "java/lang/Integer");
+        number.init(env).unwrap();
+        integer.init(env).unwrap();
+        // Number.class.isAssignableFrom(Integer.class)
+        assert!(number.is_assignable_from(env, &integer).unwrap());
+        assert!(!integer.is_assignable_from(env, &number).unwrap());
+        assert!(integer.is_assignable_from(env, &integer).unwrap());
+    });
+}
+
+#[test]
+fn primitive_class_is_not_its_wrapper() {
+    common::with_jvm(|env| {
+        let int = SpClass::primitive(env, &SpType::Int).unwrap();
+        let mut integer = SpClass::new(4641, "java/lang/Integer");
+        integer.init(env).unwrap();
+        let int = int.get_jni_class().unwrap();
+        let integer = integer.get_jni_class().unwrap();
+        assert!(!env.is_same_object(int, integer).unwrap());
+
+        let name = env
+            .call_method(int, "getName", "()Ljava/lang/String;", &[])
+            .unwrap()
+            .l()
+            .unwrap();
+        let name: String = env.get_string(&name.into()).unwrap().into();
+        assert_eq!(name, "int");
+
+        let err = SpClass::primitive(env, &SpType::new_class("java/lang/String"))
+            .err()
+            .unwrap();
+        assert!(err.to_string().starts_with("not a primitive type"));
+    });
+}
+
+#[test]
+fn preload_classes_caches_every_class() {
+    common::with_jvm(|env| {
+        preload_classes(
+            env,
+            &[
+                (4651, "java/lang/String"),
+                (4652, "java/util/ArrayList"),
+                (4653, "[I"),
+            ],
+        )
+        .unwrap();
+        assert!(SpClass::contains_cache(4651));
+        assert!(SpClass::contains_cache(4652));
+        assert!(SpClass::contains_cache(4653));
+
+        let err = preload_classes(env, &[(4654, "java/lang/Object"), (4655, "no/such/Class")])
+            .err()
+            .unwrap();
+        assert!(err.to_string().contains("key=4655"));
+        assert!(SpClass::contains_cache(4654));
+        assert!(!SpClass::contains_cache(4655));
+        env.exception_clear().unwrap();
+    });
+}
+
+#[test]
+fn array_of_resolves_int_and_string_arrays() {
+    common::with_jvm(|env| {
+        let mut ints = SpClass::array_of(4661, &SpType::Int);
+        ints.init(env).unwrap();
+        let expected = env.find_class("[I").unwrap();
+        assert!(
+            env.is_same_object(ints.get_jni_class().unwrap(), &expected)
+                .unwrap()
+        );
+
+        let mut strings = SpClass::array_of(4662, &SpType::new_class("java/lang/String"));
+        strings.init(env).unwrap();
+        let expected = env.find_class("[Ljava/lang/String;").unwrap();
+        assert!(
+            env.is_same_object(strings.get_jni_class().unwrap(), &expected)
+                .unwrap()
+        );
+    });
+}
+
+#[test]
+fn class_object_answers_get_name() {
+    common::with_jvm(|env| {
+        let mut list = SpClass::new(4671, "java/util/ArrayList");
+        list.init(env).unwrap();
+        let name = env
+            .call_method(
+                list.as_class_object().unwrap(),
+                "getName",
+                "()Ljava/lang/String;",
+                &[],
+            )
+            .unwrap()
+            .l()
+            .unwrap();
+        let name: String = env.get_string(&name.into()).unwrap().into();
+        assert_eq!(name, "java.util.ArrayList");
+    });
+}
+
+#[test]
+fn is_same_class_matches_the_exact_object_class() {
+    common::with_jvm(|env| {
+        let mut list = SpClass::new(4681, "java/util/AbstractList");
+        list.init(env).unwrap();
+        let mut array_list = SpClass::new(4682, "java/util/ArrayList");
+        array_list.init(env).unwrap();
+
+        let obj = env.new_object("java/util/ArrayList", "()V", &[]).unwrap();
+        let class = env.get_object_class(&obj).unwrap();
+        assert!(array_list.is_same_class(env, &class).unwrap());
+        // a superclass of the object's class is not the same class
+        assert!(!list.is_same_class(env, &class).unwrap());
+    });
+}
+
+#[test]
+fn ensure_initialized_runs_the_static_initializer() {
+    common::with_jvm(|env| {
+        let property = |env: &mut JNIEnv| -> bool {
+            let key = env.new_string("sp.util.clinit").unwrap();
+            !env.call_static_method(
+                "java/lang/System",
+                "getProperty",
+                "(Ljava/lang/String;)Ljava/lang/String;",
+                &[JValue::Object(&key)],
+            )
+            .unwrap()
+            .l()
+            .unwrap()
+            .is_null()
+        };
+        // `DefineClass` loads the class without initializing it
+        let loader = url_loader(env, &[]);
+        let bytes = common::class_bytes("Clinit");
+        let class = SpClass::define(env, 4691, "Clinit", &loader, &bytes).unwrap();
+        assert!(!property(env));
+
+        class.ensure_initialized(env).unwrap();
+        assert!(property(env));
+
+        // `Class.forName("int")` would throw, a primitive class is initialized already
+        let int = SpClass::primitive(env, &SpType::Int).unwrap();
+        int.ensure_initialized(env).unwrap();
+        assert!(!env.exception_check().unwrap());
+    });
+}
@@ -0,0 +1,52 @@
+mod common;
+
+use jni::objects::JObject;
+use jni_sp_util::{register_cleaner, run_cleaners, to_ptr};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+use std::time::Duration;
+
+static DROPPED: AtomicUsize = AtomicUsize::new(0);
+
+struct Native {
+    _id: u64,
+}
+
+impl Drop for Native {
+    fn drop(&mut self) {
+        DROPPED.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+fn new_object<'local>(env: &mut jni::JNIEnv<'local>) -> JObject<'local> {
+    env.new_object("java/lang/Object", "()V", &[]).unwrap()
+}
+
+/// best effort: `System.gc()` is only a hint and the reference is queued by a java thread
+/// after the collection, so the test retries for a while instead of expecting one gc to do it
+#[test]
+fn cleaner_frees_the_value_after_the_owner_is_collected() {
+    common::with_jvm(|env| {
+        let kept = new_object(env);
+        let kept = env.new_global_ref(kept).unwrap();
+        register_cleaner::<Native>(env, &kept, to_ptr(Native { _id: 1 })).unwrap();
+
+        let owner = new_object(env);
+        register_cleaner::<Native>(env, &owner, to_ptr(Native { _id: 2 })).unwrap();
+        env.delete_local_ref(owner).unwrap();
+
+        let mut freed = 0;
+        for _ in 0..100 {
+            env.call_static_method("java/lang/System", "gc", "()V", &[])
+                .unwrap();
+            freed += run_cleaners(env).unwrap();
+            if freed > 0 {
+                break;
+            }
+            thread::sleep(Duration::from_millis(50));
+        }
+        assert_eq!(freed, 1);
+        assert_eq!(DROPPED.load(Ordering::SeqCst), 1);
+        assert_eq!(run_cleaners(env).unwrap(), 0);
+    });
+}
@@ -0,0 +1,90 @@
+//! a jvm for the integration tests, loaded from the jdk at `JAVA_HOME`
+//! (or the debian default). tests skip themselves when there is no jdk.
+#![allow(dead_code)]
+
+use jni::objects::{JClass, JObject};
+use jni::sys::{JNI_OK, JNI_VERSION_1_8, JavaVMInitArgs, JavaVMOption, jint};
+use jni::{JNIEnv, JavaVM};
+use std::ffi::{CString, c_void};
+use std::sync::{Mutex, OnceLock};
+
+type CreateJavaVm =
+    unsafe extern "system" fn(*mut *mut jni::sys::JavaVM, *mut *mut c_void, *mut c_void) -> jint;
+
+static VM: OnceLock<Option<JavaVM>> = OnceLock::new();
+
+fn libjvm() -> Option<CString> {
+    let home = std::env::var("JAVA_HOME")
+        .unwrap_or_else(|_| "/usr/lib/jvm/java-17-openjdk-amd64".to_string());
+    let path = format!("{}/lib/server/libjvm.so", home);
+    std::path::Path::new(&path)
+        .exists()
+        .then(|| CString::new(path).unwrap())
+}
+
+fn create() -> Option<JavaVM> {
+    let path = libjvm()?;
+    unsafe {
+        let lib = libc::dlopen(path.as_ptr(), libc::RTLD_NOW | libc::RTLD_GLOBAL);
+        if lib.is_null() {
+            return None;
+        }
+        let sym = libc::dlsym(lib, c"JNI_CreateJavaVM".as_ptr());
+        if sym.is_null() {
+            return None;
+        }
+        let create: CreateJavaVm = std::mem::transmute(sym);
+        let mut option = JavaVMOption {
+            optionString: c"-Xrs".as_ptr() as *mut _,
+            extraInfo: std::ptr::null_mut(),
+        };
+        let mut args = JavaVMInitArgs {
+            version: JNI_VERSION_1_8,
+            nOptions: 1,
+            options: &mut option,
+            ignoreUnrecognized: 0,
+        };
+        let mut vm = std::ptr::null_mut();
+        let mut env = std::ptr::null_mut();
+        if create(&mut vm, &mut env, &mut args as *mut _ as *mut c_void) != JNI_OK {
+            return None;
+        }
+        jni_sp_util::set_java_vm(JavaVM::from_raw(vm).ok()?);
+        JavaVM::from_raw(vm).ok()
+    }
+}
+
+/// the shared jvm of this test binary, `None` when no jdk is installed
+pub fn vm() -> Option<&'static JavaVM> {
+    VM.get_or_init(create).as_ref()
+}
+
+/// run `action` on an attached thread, or skip it when there is no jvm
+pub fn with_jvm(action: impl FnOnce(&mut JNIEnv)) {
+    let Some(vm) = vm() else {
+        eprintln!("no jdk found, skipping");
+        return;
+    };
+    let mut env = vm.attach_current_thread_permanently().unwrap();
+    action(&mut env);
+}
+
+/// the `.class` bytes compiled from `tests/java/<name>.java`,
+/// rebuild them with `javac --release 8 -d tests/java tests/java/*.java`
+pub fn class_bytes(name: &str) -> Vec<u8> {
+    let path = format!("{}/tests/java/{}.class", env!("CARGO_MANIFEST_DIR"), name);
+    std::fs::read(path).unwrap()
+}
+
+static DEFINE: Mutex<()> = Mutex::new(());
+
+/// the class of `tests/java/<name>.java`, defined in the boot loader on first use
+pub fn define<'local>(env: &mut JNIEnv<'local>, name: &str) -> JClass<'local> {
+    let _guard = DEFINE.lock().unwrap();
+    if let Ok(class) = env.find_class(name) {
+        return class;
+    }
+    env.exception_clear().unwrap();
+    env.define_class(name, &JObject::null(), &class_bytes(name))
+        .unwrap()
+}
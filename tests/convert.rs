@@ -0,0 +1,66 @@
+use jni::objects::{JObject, JValue, JValueGen};
+use jni_sp_util::{FromJValue, bool_to_jboolean, jboolean_to_bool, jvalue_array, jvalues};
+
+#[test]
+fn jboolean_nonzero_is_true() {
+    assert!(!jboolean_to_bool(0));
+    assert!(jboolean_to_bool(1));
+    assert!(jboolean_to_bool(2));
+    assert!(jboolean_to_bool(0xff));
+    assert_eq!(bool_to_jboolean(true), 1);
+    assert_eq!(bool_to_jboolean(false), 0);
+}
+
+#[test]
+fn from_jvalue_extracts_the_matching_variant() {
+    assert_eq!(i32::from_jvalue(JValueGen::<JObject>::Int(7)).unwrap(), 7);
+    assert_eq!(
+        i64::from_jvalue(JValueGen::<JObject>::Long(-8)).unwrap(),
+        -8
+    );
+    assert!(bool::from_jvalue(JValueGen::<JObject>::Bool(1)).unwrap());
+    assert_eq!(
+        f64::from_jvalue(JValueGen::<JObject>::Double(0.5)).unwrap(),
+        0.5
+    );
+    assert_eq!(
+        u8::from_jvalue(JValueGen::<JObject>::Byte(-1)).unwrap(),
+        0xff
+    );
+    assert_eq!(
+        char::from_jvalue(JValueGen::<JObject>::Char(0x41)).unwrap(),
+        'A'
+    );
+}
+
+#[test]
+fn from_jvalue_rejects_another_variant() {
+    let err = i32::from_jvalue(JValueGen::<JObject>::Long(7))
+        .err()
+        .unwrap();
+    assert!(err.to_string().contains("expect int, got long"));
+    assert!(bool::from_jvalue(JValueGen::<JObject>::Int(1)).is_err());
+    assert!(char::from_jvalue(JValueGen::<JObject>::Char(0xd800)).is_err());
+}
+
+#[test]
+fn mixed_jvalues() {
+    // never handed to a jvm, only the raw pointer is compared
+    let obj = unsafe { JObject::from_raw(0x10 as jni::sys::jobject) };
+    let values = [JValue::Int(42), JValue::Object(&obj), JValue::Bool(1)];
+
+    let raw = jvalues(&values);
+    assert_eq!(raw.len(), 3);
+    unsafe {
+        assert_eq!(raw[0].i, 42);
+        assert_eq!(raw[1].l, obj.as_raw());
+        assert_eq!(raw[2].z, 1);
+    }
+
+    let raw = jvalue_array(values);
+    unsafe {
+        assert_eq!(raw[0].i, 42);
+        assert_eq!(raw[1].l, obj.as_raw());
+        assert_eq!(raw[2].z, 1);
+    }
+}
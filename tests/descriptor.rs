@@ -0,0 +1,209 @@
+use jni::signature::{JavaType, Primitive, ReturnType};
+use jni_sp_util::{
+    SpType, build_signature, is_valid_descriptor, java_native_decl, java_sig,
+    parse_method_descriptor,
+};
+
+#[test]
+fn parse_no_args_void() {
+    let (params, ret) = parse_method_descriptor("()V").unwrap();
+    assert!(params.is_empty());
+    assert!(ret == SpType::Void);
+}
+
+#[test]
+fn parse_int_to_boolean() {
+    let (params, ret) = parse_method_descriptor("(I)Z").unwrap();
+    assert!(params == [SpType::Int]);
+    assert!(ret == SpType::Boolean);
+}
+
+#[test]
+fn parse_array_and_primitive() {
+    let (params, ret) = parse_method_descriptor("([Ljava/lang/String;I)V").unwrap();
+    assert_eq!(params.len(), 2);
+    assert!(params[0] == SpType::array_of(SpType::new_class("java/lang/String"), 1));
+    assert!(params[1] == SpType::Int);
+    assert!(ret == SpType::Void);
+}
+
+#[test]
+fn parse_malformed() {
+    for descriptor in [
+        "",
+        "V",
+        "I)V",
+        "(I",
+        "(V)I",
+        "(L;)V",
+        "(Ljava/lang/String)V",
+        "(Q)V",
+        "()",
+        "()II",
+        "()[V",
+    ] {
+        assert!(
+            parse_method_descriptor(descriptor).is_err(),
+            "{} should be rejected",
+            descriptor
+        );
+    }
+}
+
+#[test]
+fn long_is_j() {
+    assert_eq!(SpType::Long.to_string(), "J");
+    assert!(SpType::from_descriptor("J").unwrap() == SpType::Long);
+    assert_eq!(build_signature(&SpType::Long, &[SpType::Long]), "(J)J");
+}
+
+#[test]
+fn valid_descriptors() {
+    for desc in [
+        "I",
+        "[[J",
+        "Ljava/lang/String;",
+        "[Ljava/util/Map$Entry;",
+        "()V",
+        "(IJ)[B",
+        "([Ljava/lang/String;I)Ljava/lang/Object;",
+    ] {
+        assert!(is_valid_descriptor(desc), "{}", desc);
+    }
+}
+
+#[test]
+fn invalid_descriptors() {
+    for desc in [
+        "",
+        "V",
+        "[V",
+        "X",
+        "L;",
+        "Ljava/lang/String",
+        "Ljava.lang.String;",
+        "II",
+        "(I",
+        "(I)",
+        "(V)V",
+        "()VI",
+        "(I)Z;",
+    ] {
+        assert!(!is_valid_descriptor(desc), "{}", desc);
+    }
+}
+
+#[test]
+fn one_char_descriptors() {
+    let expected = [
+        ('B', SpType::Byte),
+        ('C', SpType::Char),
+        ('D', SpType::Double),
+        ('F', SpType::Float),
+        ('I', SpType::Int),
+        ('J', SpType::Long),
+        ('S', SpType::Sort),
+        ('Z', SpType::Boolean),
+        ('V', SpType::Void),
+    ];
+    for (c, t) in expected {
+        assert!(SpType::try_from(c).unwrap() == t, "{}", c);
+    }
+    for c in ['L', '[', 'Q'] {
+        assert!(SpType::try_from(c).is_err(), "{} should be rejected", c);
+    }
+    let err = SpType::try_from('L').err().unwrap();
+    assert!(err.to_string().contains("from_descriptor"));
+}
+
+#[test]
+fn array_of_nests_dims_arrays() {
+    assert_eq!(SpType::array_of(SpType::Int, 2).to_string(), "[[I");
+    assert_eq!(
+        SpType::array_of(SpType::new_class("java/lang/String"), 1).to_string(),
+        "[Ljava/lang/String;"
+    );
+}
+
+#[test]
+#[should_panic(expected = "array dims must be at least 1")]
+fn array_of_zero_dims_panics() {
+    SpType::array_of(SpType::Int, 0);
+}
+
+#[test]
+#[should_panic(expected = "void can not be a method parameter type")]
+fn void_parameter_panics() {
+    build_signature(&SpType::Int, &[SpType::Int, SpType::Void]);
+}
+
+#[test]
+fn return_and_java_types() {
+    let string = SpType::new_class("java.lang.String");
+    let ints = SpType::array_of(SpType::Int, 1);
+
+    assert_eq!(
+        SpType::Int.to_return_type(),
+        ReturnType::Primitive(Primitive::Int)
+    );
+    assert_eq!(
+        SpType::Void.to_return_type(),
+        ReturnType::Primitive(Primitive::Void)
+    );
+    assert_eq!(string.to_return_type(), ReturnType::Object);
+    assert_eq!(ints.to_return_type(), ReturnType::Array);
+
+    assert_eq!(
+        SpType::Boolean.to_java_type(),
+        JavaType::Primitive(Primitive::Boolean)
+    );
+    assert_eq!(
+        string.to_java_type(),
+        JavaType::Object("java/lang/String".to_string())
+    );
+    assert_eq!(
+        ints.to_java_type(),
+        JavaType::Array(Box::new(JavaType::Primitive(Primitive::Int)))
+    );
+}
+
+#[test]
+fn java_source_names() {
+    assert_eq!(SpType::Long.java_source_name(), "long");
+    assert_eq!(SpType::Sort.java_source_name(), "short");
+    assert_eq!(
+        SpType::new_class("java/lang/String").java_source_name(),
+        "java.lang.String"
+    );
+    assert_eq!(
+        SpType::array_of(SpType::Int, 2).java_source_name(),
+        "int[][]"
+    );
+}
+
+#[test]
+fn native_declarations() {
+    assert_eq!(
+        java_native_decl("foo", true, &SpType::Long, &[("a", SpType::Int)]),
+        "public static native long foo(int a);"
+    );
+    assert_eq!(
+        java_native_decl(
+            "bar",
+            false,
+            &SpType::Void,
+            &[
+                ("name", SpType::new_class("java/lang/String")),
+                ("data", SpType::array_of(SpType::Byte, 1))
+            ]
+        ),
+        "public native void bar(java.lang.String name, byte[] data);"
+    );
+}
+
+#[test]
+fn java_sig_of_fn_types() {
+    assert_eq!(java_sig!(fn(i32, String) -> bool), "(ILjava/lang/String;)Z");
+    assert_eq!(java_sig!(fn(Vec<u8>)), "([B)V");
+    assert_eq!(java_sig!(fn() -> i64), "()J");
+}
@@ -0,0 +1,30 @@
+#![cfg(feature = "diagnostics")]
+
+mod common;
+
+use jni_sp_util::{SpClass, SpMethod, SpStaticMethod, SpType, dump_caches};
+
+#[test]
+fn dump_lists_method_arity() {
+    common::with_jvm(|env| {
+        let class = env.find_class("java/lang/String").unwrap();
+        SpMethod::new(4401, "charAt", &SpType::Char, &[SpType::Int])
+            .init(env, &class)
+            .unwrap();
+        SpStaticMethod::new(
+            4402,
+            "valueOf",
+            &SpType::Class(SpClass::from_sig("java/lang/String")),
+            &[SpType::Int],
+        )
+        .init(env, &class)
+        .unwrap();
+
+        let dump = dump_caches();
+        assert!(dump.methods.contains(&(4401, 1)));
+        assert!(dump.static_methods.contains(&(4402, 1)));
+        let text = dump.to_string();
+        assert!(text.contains("methods (key/arity) (1): 4401/1"));
+        assert!(text.contains("static methods (key/arity) (1): 4402/1"));
+    });
+}
@@ -0,0 +1,31 @@
+mod common;
+
+use jni::objects::JObject;
+use jni::sys::JNI_VERSION_1_8;
+use jni_sp_util::{RefKind, jni_version, ref_type};
+
+#[test]
+fn jni_version_is_at_least_the_requested_one() {
+    common::with_jvm(|env| {
+        // the test jvm is created with `JNI_VERSION_1_8`
+        let version = jni_version(env).unwrap();
+        assert!(version >= JNI_VERSION_1_8, "version {:#x}", version);
+    });
+}
+
+#[test]
+fn ref_type_tells_local_global_and_null_refs_apart() {
+    common::with_jvm(|env| {
+        let local = env.new_string("kind").unwrap();
+        assert_eq!(ref_type(env, &local).unwrap(), RefKind::Local);
+
+        let global = env.new_global_ref(&local).unwrap();
+        assert_eq!(ref_type(env, global.as_obj()).unwrap(), RefKind::Global);
+
+        let weak = env.new_weak_ref(&local).unwrap().unwrap();
+        let weak_obj = unsafe { JObject::from_raw(weak.as_raw()) };
+        assert_eq!(ref_type(env, &weak_obj).unwrap(), RefKind::WeakGlobal);
+
+        assert_eq!(ref_type(env, &JObject::null()).unwrap(), RefKind::Invalid);
+    });
+}
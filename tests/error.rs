@@ -0,0 +1,117 @@
+mod common;
+
+use jni_sp_util::{JniBoundaryError, Result, anyhow, retry_on_oom, throw_with_cause, try_jni};
+
+#[test]
+fn throw_with_cause_wraps_the_pending_exception() {
+    common::with_jvm(|env| {
+        env.throw_new("java/lang/ArithmeticException", "inner")
+            .unwrap();
+        throw_with_cause(env, "java/lang/IllegalStateException", "outer").unwrap();
+
+        let thrown = env.exception_occurred().unwrap();
+        env.exception_clear().unwrap();
+        assert!(
+            env.is_instance_of(&thrown, "java/lang/IllegalStateException")
+                .unwrap()
+        );
+        let cause = env
+            .call_method(&thrown, "getCause", "()Ljava/lang/Throwable;", &[])
+            .unwrap()
+            .l()
+            .unwrap();
+        assert!(
+            env.is_instance_of(&cause, "java/lang/ArithmeticException")
+                .unwrap()
+        );
+    });
+}
+
+#[test]
+fn throw_with_cause_keeps_the_cause_when_the_wrapper_fails() {
+    common::with_jvm(|env| {
+        env.throw_new("java/lang/ArithmeticException", "inner")
+            .unwrap();
+        assert!(throw_with_cause(env, "no/such/Exception", "outer").is_err());
+
+        let thrown = env.exception_occurred().unwrap();
+        env.exception_clear().unwrap();
+        assert!(
+            env.is_instance_of(&thrown, "java/lang/ArithmeticException")
+                .unwrap()
+        );
+    });
+}
+
+#[test]
+fn try_jni_returns_value_error_and_panic() {
+    common::with_jvm(|env| {
+        let value = try_jni(env, |env| {
+            let text = env.new_string("12")?;
+            Ok(env.get_string(&text)?.to_str()?.len())
+        });
+        assert_eq!(value.unwrap(), 2);
+
+        let error = try_jni(env, |env| {
+            let text = env.new_string("x")?;
+            env.call_static_method(
+                "java/lang/Integer",
+                "parseInt",
+                "(Ljava/lang/String;)I",
+                &[(&text).into()],
+            )?;
+            Ok(())
+        });
+        assert!(matches!(error, Err(JniBoundaryError::Error(_))));
+        assert!(env.exception_check().unwrap());
+        env.exception_clear().unwrap();
+
+        let panic = try_jni(env, |_| -> jni_sp_util::Result<()> { panic!("boom") });
+        match panic {
+            Err(JniBoundaryError::Panic(msg)) => assert_eq!(msg, "boom"),
+            other => panic!("expected a panic, got {:?}", other),
+        }
+    });
+}
+
+#[test]
+fn retry_on_oom_cleans_up_and_runs_the_action_again() {
+    common::with_jvm(|env| {
+        let mut calls = 0;
+        let mut cleaned = false;
+        let value = retry_on_oom(
+            env,
+            |env| {
+                calls += 1;
+                if calls == 1 {
+                    env.throw_new("java/lang/OutOfMemoryError", "once")?;
+                    return Err(anyhow!("out of memory"));
+                }
+                Ok(calls)
+            },
+            |_| cleaned = true,
+        )
+        .unwrap();
+        assert_eq!(value, 2);
+        assert!(cleaned);
+        assert!(!env.exception_check().unwrap());
+
+        // any other exception is left pending and not retried
+        let mut calls = 0;
+        let err = retry_on_oom(
+            env,
+            |env| -> Result<()> {
+                calls += 1;
+                env.throw_new("java/lang/IllegalStateException", "no retry")?;
+                Err(anyhow!("failed"))
+            },
+            |_| unreachable!(),
+        )
+        .err()
+        .unwrap();
+        assert_eq!(err.to_string(), "failed");
+        assert_eq!(calls, 1);
+        assert!(env.exception_check().unwrap());
+        env.exception_clear().unwrap();
+    });
+}
@@ -0,0 +1,209 @@
+mod common;
+
+use jni::JNIEnv;
+use jni::objects::{JObject, JValue};
+use jni::signature::{JavaType, Primitive, ReturnType};
+use jni_sp_util::{
+    FieldKind, SpClass, SpField, SpMethod, SpStaticField, SpType, construct_and_set, read_fields,
+    write_fields,
+};
+
+const INT: ReturnType = ReturnType::Primitive(Primitive::Int);
+
+#[test]
+fn construct_and_set_writes_two_fields() {
+    common::with_jvm(|env| {
+        let mut class = SpClass::new(4401, "java/awt/Point");
+        class.init(env).unwrap();
+        let jclass = env.find_class("java/awt/Point").unwrap();
+        let ctor = SpMethod::new(4401, "<init>", &SpType::Void, &[]);
+        ctor.init(env, &jclass).unwrap();
+        let x = SpField::new(4401, "x", &SpType::Int);
+        let y = SpField::new(4402, "y", &SpType::Int);
+        x.init(env, &jclass).unwrap();
+        y.init(env, &jclass).unwrap();
+
+        let point = construct_and_set(
+            env,
+            &class,
+            &ctor,
+            &[],
+            &[(&x, JValue::Int(3)), (&y, JValue::Int(4))],
+        )
+        .unwrap();
+        assert_eq!(x.call(env, &point, INT).unwrap().i().unwrap(), 3);
+        assert_eq!(y.call(env, &point, INT).unwrap().i().unwrap(), 4);
+
+        let extra = [JValue::Int(1).as_jni()];
+        let err = construct_and_set(env, &class, &ctor, &extra, &[])
+            .err()
+            .unwrap();
+        assert!(err.to_string().contains("expects 0 arguments, got 1"));
+    });
+}
+
+fn string_field(env: &mut jni::JNIEnv, obj: &JObject, field: &SpField) -> String {
+    let value = field
+        .call(env, obj, ReturnType::Object)
+        .unwrap()
+        .l()
+        .unwrap();
+    env.get_string(&value.into()).unwrap().into()
+}
+
+#[test]
+fn final_field_is_kept_and_plain_field_is_read_again() {
+    common::with_jvm(|env| {
+        let name = "java/util/AbstractMap$SimpleImmutableEntry";
+        let jclass = env.find_class(name).unwrap();
+        let object = SpType::Class(SpClass::from_sig("java/lang/Object"));
+        let kept = SpField::new_with_kind(4411, "key", &object, FieldKind::Final);
+        let plain = SpField::new(4412, "key", &object);
+        kept.init(env, &jclass).unwrap();
+        plain.init(env, &jclass).unwrap();
+
+        let first = env.new_string("first").unwrap();
+        let entry = env
+            .new_object(
+                &jclass,
+                "(Ljava/lang/Object;Ljava/lang/Object;)V",
+                &[JValue::Object(&first), JValue::Object(&JObject::null())],
+            )
+            .unwrap();
+        assert_eq!(string_field(env, &entry, &kept), "first");
+
+        let second = env.new_string("second").unwrap();
+        env.set_field(&entry, "key", "Ljava/lang/Object;", JValue::Object(&second))
+            .unwrap();
+        assert_eq!(string_field(env, &entry, &kept), "first");
+        assert_eq!(string_field(env, &entry, &plain), "second");
+    });
+}
+
+#[test]
+fn field_kind_is_checked_by_init() {
+    common::with_jvm(|env| {
+        let point = env.find_class("java/awt/Point").unwrap();
+        let x = SpField::new_with_kind(4413, "x", &SpType::Int, FieldKind::Final);
+        let err = x.init(env, &point).err().unwrap();
+        assert!(err.to_string().contains("is not final"));
+
+        let atomic = env
+            .find_class("java/util/concurrent/atomic/AtomicInteger")
+            .unwrap();
+        let value = SpField::new_with_kind(4414, "value", &SpType::Int, FieldKind::Volatile);
+        value.init(env, &atomic).unwrap();
+        let counter = env.new_object(&atomic, "(I)V", &[JValue::Int(7)]).unwrap();
+        assert_eq!(value.call(env, &counter, INT).unwrap().i().unwrap(), 7);
+    });
+}
+
+#[test]
+fn set_checks_the_value_type() {
+    common::with_jvm(|env| {
+        let jclass = env.find_class("java/awt/Point").unwrap();
+        let x = SpField::new(4421, "x", &SpType::Int);
+        x.init(env, &jclass).unwrap();
+        let point = env.new_object(&jclass, "()V", &[]).unwrap();
+
+        let err = x.set(env, &point, JValue::Bool(1)).err().unwrap();
+        assert_eq!(err.to_string(), "field type error: key=4421 is I, got bool");
+        x.set(env, &point, JValue::Int(9)).unwrap();
+        assert_eq!(x.call(env, &point, INT).unwrap().i().unwrap(), 9);
+    });
+}
+
+#[test]
+fn read_fields_reads_every_field() {
+    common::with_jvm(|env| {
+        let jclass = env.find_class("java/awt/Point").unwrap();
+        let x = SpField::new(4431, "x", &SpType::Int);
+        let y = SpField::new(4432, "y", &SpType::Int);
+        x.init(env, &jclass).unwrap();
+        y.init(env, &jclass).unwrap();
+        let point = env
+            .new_object(&jclass, "(II)V", &[JValue::Int(3), JValue::Int(4)])
+            .unwrap();
+
+        let values = read_fields(env, &point, &[&x, &y], &[INT, INT]).unwrap();
+        let values: Vec<i32> = values.into_iter().map(|v| v.i().unwrap()).collect();
+        assert_eq!(values, [3, 4]);
+
+        let err = read_fields(env, &point, &[&x, &y], &[INT]).err().unwrap();
+        assert_eq!(
+            err.to_string(),
+            "read fields error: 2 fields but 1 return types"
+        );
+    });
+}
+
+#[test]
+fn write_fields_then_read_them_back() {
+    common::with_jvm(|env| {
+        let class = common::define(env, "Fields");
+        let fields = [
+            SpField::new(4441, "count", &SpType::Int),
+            SpField::new(4442, "total", &SpType::Long),
+            SpField::new(4443, "done", &SpType::Boolean),
+            SpField::new(4444, "ratio", &SpType::Double),
+            SpField::new(4445, "label", &SpType::new_class("java/lang/String")),
+        ];
+        for field in &fields {
+            field.init(env, &class).unwrap();
+        }
+        let obj = env.new_object(&class, "()V", &[]).unwrap();
+        let label = env.new_string("five").unwrap();
+
+        write_fields(
+            env,
+            &obj,
+            &[
+                (&fields[0], JValue::Int(5)),
+                (&fields[1], JValue::Long(1 << 40)),
+                (&fields[2], JValue::Bool(1)),
+                (&fields[3], JValue::Double(0.25)),
+                (&fields[4], JValue::Object(&label)),
+            ],
+        )
+        .unwrap();
+
+        assert_eq!(fields[0].call(env, &obj, INT).unwrap().i().unwrap(), 5);
+        let total = ReturnType::Primitive(Primitive::Long);
+        assert_eq!(
+            fields[1].call(env, &obj, total).unwrap().j().unwrap(),
+            1 << 40
+        );
+        assert!(fields[2].get_bool(env, &obj).unwrap());
+        let ratio = ReturnType::Primitive(Primitive::Double);
+        assert_eq!(fields[3].call(env, &obj, ratio).unwrap().d().unwrap(), 0.25);
+        assert_eq!(string_field(env, &obj, &fields[4]), "five");
+    });
+}
+
+#[test]
+fn cached_static_object_is_read_again_after_invalidate() {
+    common::with_jvm(|env| {
+        let class = common::define(env, "Fields");
+        let string = SpType::new_class("java/lang/String");
+        let shared = SpStaticField::new(4451, "shared", &string);
+        shared.init(env, &class).unwrap();
+        let read = |env: &mut JNIEnv| -> String {
+            let value = shared
+                .call_cached_object(env, &class, JavaType::Object("java/lang/String".into()))
+                .unwrap();
+            env.get_string(&value.into()).unwrap().into()
+        };
+        assert_eq!(read(env), "first");
+
+        let second = env.new_string("second").unwrap();
+        let id = env
+            .get_static_field_id(&class, "shared", "Ljava/lang/String;")
+            .unwrap();
+        env.set_static_field(&class, id, JValue::Object(&second))
+            .unwrap();
+        assert_eq!(read(env), "first");
+
+        SpStaticField::invalidate_value(4451);
+        assert_eq!(read(env), "second");
+    });
+}
@@ -0,0 +1,104 @@
+//! round trip of random `SpType` trees through their descriptor,
+//! seeded so a failure reproduces. `corpus.txt` holds descriptors that must round trip too
+
+use jni_sp_util::{SpClass, SpType, build_signature, is_valid_descriptor, parse_method_descriptor};
+
+const CASES: usize = 4096;
+const MAX_ARRAY_DEPTH: usize = 5;
+
+/// xorshift64*, good enough to spread the cases and keeps the test free of dependencies
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        self.0.wrapping_mul(0x2545_f491_4f6c_dd1d)
+    }
+
+    fn below(&mut self, n: usize) -> usize {
+        (self.next() % n as u64) as usize
+    }
+
+    fn pick<'a, T>(&mut self, items: &'a [T]) -> &'a T {
+        &items[self.below(items.len())]
+    }
+}
+
+const NAME_CHARS: &[char] = &[
+    'a', 'b', 'z', 'A', 'Q', 'Z', '0', '7', '_', '$', 'é', 'ß', '中',
+];
+
+fn class_name(rng: &mut Rng) -> String {
+    let segments = 1 + rng.below(4);
+    (0..segments)
+        .map(|_| {
+            let len = 1 + rng.below(8);
+            (0..len).map(|_| *rng.pick(NAME_CHARS)).collect::<String>()
+        })
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// a field type, never `Void`
+fn field_type(rng: &mut Rng) -> SpType {
+    let element = match rng.below(9) {
+        0 => SpType::Byte,
+        1 => SpType::Char,
+        2 => SpType::Double,
+        3 => SpType::Float,
+        4 => SpType::Int,
+        5 => SpType::Long,
+        6 => SpType::Sort,
+        7 => SpType::Boolean,
+        _ => SpType::Class(SpClass::from_sig(&class_name(rng))),
+    };
+    (0..rng.below(MAX_ARRAY_DEPTH + 1)).fold(element, |t, _| SpType::Array(Box::new(t)))
+}
+
+fn check_field_type(t: &SpType) {
+    let text = t.to_string();
+    let parsed = SpType::from_descriptor(&text).unwrap_or_else(|e| panic!("{}: {}", text, e));
+    assert!(parsed == *t, "{} does not round trip", text);
+    assert_eq!(text.len(), t.get_str_len(), "{}", text);
+    let mut written = String::new();
+    t.write_descriptor(&mut written);
+    assert_eq!(written, text);
+    assert!(is_valid_descriptor(&text), "{}", text);
+}
+
+#[test]
+fn random_types_round_trip() {
+    let mut rng = Rng(0x5eed_1234_abcd_0001);
+    for _ in 0..CASES {
+        check_field_type(&field_type(&mut rng));
+    }
+}
+
+#[test]
+fn random_method_descriptors_round_trip() {
+    let mut rng = Rng(0x5eed_1234_abcd_0002);
+    for _ in 0..CASES / 4 {
+        let params: Vec<SpType> = (0..rng.below(6)).map(|_| field_type(&mut rng)).collect();
+        let ret = if rng.below(4) == 0 {
+            SpType::Void
+        } else {
+            field_type(&mut rng)
+        };
+        let sig = build_signature(&ret, &params);
+        assert!(is_valid_descriptor(&sig), "{}", sig);
+        let (parsed_params, parsed_ret) = parse_method_descriptor(&sig).unwrap();
+        assert!(parsed_params == params, "{}", sig);
+        assert!(parsed_ret == ret, "{}", sig);
+    }
+}
+
+#[test]
+fn corpus_round_trips() {
+    for line in include_str!("corpus.txt").lines().filter(|l| !l.is_empty()) {
+        let t = SpType::from_descriptor(line).unwrap_or_else(|e| panic!("{}: {}", line, e));
+        assert_eq!(t.to_string(), line);
+        check_field_type(&t);
+    }
+}
@@ -0,0 +1,45 @@
+mod common;
+
+use jni::objects::JObject;
+use jni_sp_util::{debug_to_string, option_to_optional, optional_to_option};
+
+#[test]
+fn present_optional_is_some() {
+    common::with_jvm(|env| {
+        let value = env.new_string("value").unwrap();
+        let optional = option_to_optional(env, Some(&value)).unwrap();
+        let back = optional_to_option(env, &optional).unwrap().unwrap();
+        assert!(env.is_same_object(&back, &value).unwrap());
+    });
+}
+
+#[test]
+fn empty_optional_is_none() {
+    common::with_jvm(|env| {
+        let optional = option_to_optional(env, None).unwrap();
+        assert!(!optional.is_null());
+        assert!(optional_to_option(env, &optional).unwrap().is_none());
+
+        let null = JObject::null();
+        let optional = option_to_optional(env, Some(&null)).unwrap();
+        assert!(optional_to_option(env, &optional).unwrap().is_none());
+    });
+}
+
+#[test]
+fn null_optional_is_none() {
+    common::with_jvm(|env| {
+        assert!(optional_to_option(env, &JObject::null()).unwrap().is_none());
+    });
+}
+
+#[test]
+fn debug_to_string_of_an_object_and_null() {
+    common::with_jvm(|env| {
+        let value = env
+            .new_object("java/lang/Integer", "(I)V", &[42.into()])
+            .unwrap();
+        assert_eq!(debug_to_string(env, &value).unwrap(), "42");
+        assert_eq!(debug_to_string(env, &JObject::null()).unwrap(), "null");
+    });
+}
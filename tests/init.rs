@@ -0,0 +1,71 @@
+#![cfg(feature = "log")]
+
+mod common;
+
+use jni_sp_util::SpClass;
+use log::{Level, LevelFilter, Log, Metadata, Record};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Barrier};
+use std::thread;
+
+/// counts the cache miss traces of class keys 4200.., each one is followed by a `find_class`
+struct MissCounter(AtomicUsize);
+
+impl Log for MissCounter {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= Level::Trace
+    }
+
+    fn log(&self, record: &Record) {
+        if record
+            .args()
+            .to_string()
+            .starts_with("class cache miss: key=420")
+        {
+            self.0.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+static MISSES: MissCounter = MissCounter(AtomicUsize::new(0));
+
+#[test]
+fn concurrent_class_init_finds_the_class_once() {
+    let Some(vm) = common::vm() else {
+        eprintln!("no jdk found, skipping");
+        return;
+    };
+    log::set_logger(&MISSES).unwrap();
+    log::set_max_level(LevelFilter::Trace);
+
+    let classes = [
+        "java/util/concurrent/ConcurrentSkipListMap",
+        "java/util/concurrent/ConcurrentSkipListSet",
+        "java/util/concurrent/CopyOnWriteArrayList",
+        "java/util/concurrent/LinkedTransferQueue",
+        "java/util/concurrent/Phaser",
+        "java/util/concurrent/Exchanger",
+    ];
+    let threads = 16;
+    for (key, name) in (4200..).zip(classes) {
+        let barrier = Arc::new(Barrier::new(threads));
+        let handles: Vec<_> = (0..threads)
+            .map(|_| {
+                let barrier = barrier.clone();
+                thread::spawn(move || {
+                    let mut env = vm.attach_current_thread_permanently().unwrap();
+                    let mut class = SpClass::new(key, name);
+                    barrier.wait();
+                    class.init(&mut env).unwrap();
+                    assert!(class.get_jni_class().is_ok());
+                })
+            })
+            .collect();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+    }
+    assert_eq!(MISSES.0.load(Ordering::SeqCst), classes.len());
+}
@@ -0,0 +1,6 @@
+/** a null byte[] return for the call_byte_array tests */
+public class Bytes {
+    public static byte[] none() {
+        return null;
+    }
+}
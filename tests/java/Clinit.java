@@ -0,0 +1,6 @@
+/** marks in a system property that its static initializer ran, for the ensure_initialized test */
+public class Clinit {
+    static {
+        System.setProperty("sp.util.clinit", "ran");
+    }
+}
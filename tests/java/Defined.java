@@ -0,0 +1,6 @@
+/** defined from its bytes by the tests */
+public class Defined {
+    public static String greet(String name) {
+        return "hello " + name;
+    }
+}
@@ -0,0 +1,9 @@
+/** one field of each kind for the field write tests */
+public class Fields {
+    public int count;
+    public long total;
+    public boolean done;
+    public double ratio;
+    public String label;
+    public static String shared = "first";
+}
@@ -0,0 +1,6 @@
+/** found only through a class loader pointed at tests/java, never by find_class */
+public class Loaded {
+    public static int answer() {
+        return 42;
+    }
+}
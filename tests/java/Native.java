@@ -0,0 +1,4 @@
+/** a native method for the register and unregister natives tests */
+public class Native {
+    public static native int twice(int value);
+}
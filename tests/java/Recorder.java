@@ -0,0 +1,15 @@
+import java.util.function.BiConsumer;
+
+/** a callback for the tests, keeps the arguments of the last accept */
+public class Recorder implements BiConsumer<Object, Object> {
+    public int calls;
+    public Object result;
+    public Object error;
+
+    @Override
+    public synchronized void accept(Object result, Object error) {
+        this.calls++;
+        this.result = result;
+        this.error = error;
+    }
+}
@@ -0,0 +1,535 @@
+mod common;
+
+use jni::objects::{JObject, JString, JValue, JValueOwned};
+use jni::signature::{Primitive, ReturnType};
+use jni_sp_util::{
+    NullPolicy, RefKind, SpClass, SpMethod, SpStaticMethod, SpType, get_sp_struct, ref_type,
+};
+
+#[test]
+fn method_call_checks_the_argument_count() {
+    common::with_jvm(|env| {
+        let class = env.find_class("java/lang/String").unwrap();
+        let length = SpMethod::new(4301, "length", &SpType::Int, &[]);
+        length.init(env, &class).unwrap();
+
+        let text = env.new_string("four").unwrap();
+        let extra = [JValue::Int(1).as_jni()];
+        let err = length
+            .call(env, &text, &extra, ReturnType::Primitive(Primitive::Int))
+            .err()
+            .unwrap();
+        assert!(err.to_string().contains("expects 0 arguments, got 1"));
+
+        let value = length
+            .call(env, &text, &[], ReturnType::Primitive(Primitive::Int))
+            .unwrap();
+        assert_eq!(value.i().unwrap(), 4);
+    });
+}
+
+#[test]
+fn method_reports_its_declaring_class() {
+    common::with_jvm(|env| {
+        let class = env.find_class("java/lang/StringBuilder").unwrap();
+        let method = SpMethod::new(
+            4311,
+            "reverse",
+            &SpType::Class(SpClass::from_sig("java/lang/StringBuilder")),
+            &[],
+        )
+        .with_declaring_class("java.lang.StringBuilder");
+        method.init(env, &class).unwrap();
+        assert_eq!(method.declaring_class(), Some("java/lang/StringBuilder"));
+
+        let missing = SpMethod::new(4312, "noSuchMethod", &SpType::Void, &[])
+            .with_declaring_class("java/lang/StringBuilder");
+        let err = missing.init(env, &class).err().unwrap();
+        env.exception_clear().unwrap();
+        assert!(format!("{:#}", err).contains("java/lang/StringBuilder.noSuchMethod ()V"));
+
+        let plain = SpMethod::new(4313, "noSuchMethod", &SpType::Void, &[]);
+        assert!(plain.declaring_class().is_none());
+        let err = plain.init(env, &class).err().unwrap();
+        env.exception_clear().unwrap();
+        assert!(format!("{:#}", err).contains("init method noSuchMethod ()V"));
+        assert!(SpMethod::cache(4311).declaring_class().is_none());
+    });
+}
+
+#[test]
+fn call_noargs_only_takes_methods_without_parameters() {
+    common::with_jvm(|env| {
+        let class = env.find_class("java/lang/String").unwrap();
+        let is_empty = SpMethod::new(4321, "isEmpty", &SpType::Boolean, &[]);
+        is_empty.init(env, &class).unwrap();
+        let char_at = SpMethod::new(4322, "charAt", &SpType::Char, &[SpType::Int]);
+        char_at.init(env, &class).unwrap();
+
+        let text = env.new_string("").unwrap();
+        let value = is_empty
+            .call_noargs(env, &text, ReturnType::Primitive(Primitive::Boolean))
+            .unwrap();
+        assert!(value.z().unwrap());
+
+        let err = char_at
+            .call_noargs(env, &text, ReturnType::Primitive(Primitive::Char))
+            .err()
+            .unwrap();
+        assert!(err.to_string().contains("with 1 parameters"));
+    });
+}
+
+#[test]
+fn get_sp_struct_names_the_key_of_a_bad_method() {
+    common::with_jvm(|env| {
+        let class = env.find_class("java/lang/String").unwrap();
+        let err = get_sp_struct!(method: env, 4331, &class, "noSuchMethod", &SpType::Int, &[])
+            .err()
+            .unwrap();
+        env.exception_clear().unwrap();
+        assert_eq!(
+            err.to_string(),
+            "get_sp_struct method: key=4331, name=noSuchMethod"
+        );
+        assert!(!SpMethod::contains_cache(4331));
+    });
+}
+
+#[test]
+fn interface_default_method_runs_on_an_implementation() {
+    common::with_jvm(|env| {
+        let collection = env.find_class("java/util/Collection").unwrap();
+        let stream = SpMethod::new(
+            4341,
+            "stream",
+            &SpType::new_class("java/util/stream/Stream"),
+            &[],
+        );
+        stream.init(env, &collection).unwrap();
+
+        let list = env.new_object("java/util/ArrayList", "()V", &[]).unwrap();
+        let result = stream
+            .call(env, &list, &[], ReturnType::Object)
+            .unwrap()
+            .l()
+            .unwrap();
+        assert!(
+            env.is_instance_of(&result, "java/util/stream/Stream")
+                .unwrap()
+        );
+    });
+}
+
+#[test]
+fn static_call_string() {
+    common::with_jvm(|env| {
+        let class = env.find_class("java/lang/String").unwrap();
+        let value_of = SpStaticMethod::new(
+            4351,
+            "valueOf",
+            &SpType::new_class("java/lang/String"),
+            &[SpType::Int],
+        );
+        value_of.init(env, &class).unwrap();
+        let value = value_of
+            .call_string(env, &class, &[JValue::Int(-42).as_jni()])
+            .unwrap();
+        assert_eq!(value.as_deref(), Some("-42"));
+    });
+}
+
+#[test]
+fn call_global_passes_global_refs() {
+    common::with_jvm(|env| {
+        let class = env.find_class("java/lang/String").unwrap();
+        let sequence = SpType::new_class("java/lang/CharSequence");
+        let replace = SpMethod::new(
+            4361,
+            "replace",
+            &SpType::new_class("java/lang/String"),
+            &[sequence, SpType::new_class("java/lang/CharSequence")],
+        );
+        replace.init(env, &class).unwrap();
+
+        let target = env.new_string("X").unwrap();
+        let target = env.new_global_ref(target).unwrap();
+        let replacement = env.new_string("-").unwrap();
+        let replacement = env.new_global_ref(replacement).unwrap();
+        let text = env.new_string("aXbX").unwrap();
+        let value = replace
+            .call_global(env, &text, &[&target, &replacement], ReturnType::Object)
+            .unwrap()
+            .l()
+            .unwrap();
+        let value: String = env.get_string(&value.into()).unwrap().into();
+        assert_eq!(value, "a-b-");
+    });
+}
+
+#[test]
+fn static_call_checks_the_argument_count() {
+    common::with_jvm(|env| {
+        let class = env.find_class("java/lang/Integer").unwrap();
+        let parse = SpStaticMethod::new(
+            4371,
+            "parseInt",
+            &SpType::Int,
+            &[SpType::new_class("java/lang/String")],
+        );
+        parse.init(env, &class).unwrap();
+
+        let err = parse
+            .call(env, &class, &[], ReturnType::Primitive(Primitive::Int))
+            .err()
+            .unwrap();
+        assert_eq!(
+            err.to_string(),
+            "static method arity error: key=4371 expects 1 arguments, got 0"
+        );
+    });
+}
+
+#[test]
+fn call_nullable_passes_null_and_rejects_primitive_parameters() {
+    common::with_jvm(|env| {
+        let class = env.find_class("java/lang/String").unwrap();
+        let equals = SpMethod::new(
+            4381,
+            "equals",
+            &SpType::Boolean,
+            &[SpType::Class(SpClass::from_sig("java/lang/Object"))],
+        );
+        equals.init(env, &class).unwrap();
+        let char_at = SpMethod::new(4382, "charAt", &SpType::Char, &[SpType::Int]);
+        char_at.init(env, &class).unwrap();
+
+        let text = env.new_string("text").unwrap();
+        let same = equals
+            .call_nullable(
+                env,
+                &text,
+                &[None],
+                ReturnType::Primitive(Primitive::Boolean),
+            )
+            .unwrap();
+        assert!(!same.z().unwrap());
+
+        let err = char_at
+            .call_nullable(env, &text, &[None], ReturnType::Primitive(Primitive::Char))
+            .err()
+            .unwrap();
+        assert!(err.to_string().contains("argument 0 of (I)C is primitive"));
+    });
+}
+
+#[test]
+fn forgotten_method_misses_until_init() {
+    common::with_jvm(|env| {
+        let class = env.find_class("java/lang/String").unwrap();
+        let length = SpMethod::new(4391, "length", &SpType::Int, &[]);
+        length.init(env, &class).unwrap();
+        let text = env.new_string("four").unwrap();
+        let int = || ReturnType::Primitive(Primitive::Int);
+        assert_eq!(length.call(env, &text, &[], int()).unwrap().i().unwrap(), 4);
+
+        SpMethod::forget(4391);
+        assert!(!SpMethod::contains_cache(4391));
+        let err = length.call(env, &text, &[], int()).err().unwrap();
+        assert!(err.to_string().contains("no method cache"));
+
+        length.init(env, &class).unwrap();
+        assert_eq!(length.call(env, &text, &[], int()).unwrap().i().unwrap(), 4);
+    });
+}
+
+#[test]
+fn call_byte_array_copies_bytes_and_applies_the_null_policy() {
+    common::with_jvm(|env| {
+        let string = env.find_class("java/lang/String").unwrap();
+        let bytes = SpType::array_of(SpType::Byte, 1);
+        let get_bytes = SpMethod::new(4392, "getBytes", &bytes, &[]);
+        get_bytes.init(env, &string).unwrap();
+        let text = env.new_string("abc").unwrap();
+        let copied = get_bytes
+            .call_byte_array(env, &text, &[], NullPolicy::Error)
+            .unwrap();
+        assert_eq!(copied, b"abc");
+
+        let class = common::define(env, "Bytes");
+        let none = SpStaticMethod::new(4393, "none", &bytes, &[]);
+        none.init(env, &class).unwrap();
+        let empty = none
+            .call_byte_array(env, &class, &[], NullPolicy::Empty)
+            .unwrap();
+        assert!(empty.is_empty());
+        let err = none
+            .call_byte_array(env, &class, &[], NullPolicy::Error)
+            .err()
+            .unwrap();
+        assert!(err.to_string().contains("byte array is null"));
+    });
+}
+
+#[test]
+fn call_object_opt_maps_null_to_none() {
+    common::with_jvm(|env| {
+        let class = env.find_class("java/util/HashMap").unwrap();
+        let object = SpType::new_class("java/lang/Object");
+        let get = SpMethod::new(4394, "get", &object, std::slice::from_ref(&object));
+        get.init(env, &class).unwrap();
+
+        let map = env.new_object(&class, "()V", &[]).unwrap();
+        let key = env.new_string("key").unwrap();
+        let args = [JValue::Object(&key).as_jni()];
+        assert!(get.call_object_opt(env, &map, &args).unwrap().is_none());
+
+        env.call_method(
+            &map,
+            "put",
+            "(Ljava/lang/Object;Ljava/lang/Object;)Ljava/lang/Object;",
+            &[JValue::Object(&key), JValue::Object(&key)],
+        )
+        .unwrap();
+        let value = get.call_object_opt(env, &map, &args).unwrap().unwrap();
+        assert!(env.is_same_object(&value, &key).unwrap());
+    });
+}
+
+#[test]
+fn call_char_reads_string_char_at() {
+    common::with_jvm(|env| {
+        let class = env.find_class("java/lang/String").unwrap();
+        let char_at = SpMethod::new(4395, "charAt", &SpType::Char, &[SpType::Int]);
+        char_at.init(env, &class).unwrap();
+
+        let text = env.new_string("héllo😀").unwrap();
+        let at = |i: i32| [JValue::Int(i).as_jni()];
+        assert_eq!(char_at.call_char(env, &text, &at(0)).unwrap(), 'h');
+        assert_eq!(char_at.call_char(env, &text, &at(1)).unwrap(), 'é');
+        // half of a surrogate pair is not a rust char
+        let err = char_at.call_char(env, &text, &at(5)).err().unwrap();
+        assert!(err.to_string().contains("lone surrogate"));
+    });
+}
+
+#[test]
+fn call_auto_local_deletes_the_ref_on_drop() {
+    common::with_jvm(|env| {
+        let class = env.find_class("java/lang/String").unwrap();
+        let trim = SpMethod::new(4396, "trim", &SpType::new_class("java/lang/String"), &[]);
+        trim.init(env, &class).unwrap();
+
+        let text = env.new_string(" padded ").unwrap();
+        let trimmed = trim.call_auto_local(env, &text, &[]).unwrap();
+        assert_eq!(ref_type(env, &trimmed).unwrap(), RefKind::Local);
+        let raw = trimmed.as_raw();
+        drop(trimmed);
+
+        // hotspot still reports a deleted slot as local, but it no longer holds the object
+        let stale = unsafe { JObject::from_raw(raw) };
+        assert!(env.new_local_ref(&stale).unwrap().is_null());
+    });
+}
+
+#[test]
+fn call_short_and_call_byte_keep_negative_values() {
+    common::with_jvm(|env| {
+        let class = env.find_class("java/lang/Integer").unwrap();
+        let short_value = SpMethod::new(4397, "shortValue", &SpType::Sort, &[]);
+        short_value.init(env, &class).unwrap();
+        let byte_value = SpMethod::new(4398, "byteValue", &SpType::Byte, &[]);
+        byte_value.init(env, &class).unwrap();
+
+        let boxed = env
+            .call_static_method(
+                &class,
+                "valueOf",
+                "(I)Ljava/lang/Integer;",
+                &[JValue::Int(-300)],
+            )
+            .unwrap()
+            .l()
+            .unwrap();
+        assert_eq!(short_value.call_short(env, &boxed, &[]).unwrap(), -300);
+        // java narrows -300 to the byte 0xd4
+        assert_eq!(byte_value.call_byte(env, &boxed, &[]).unwrap(), -44);
+    });
+}
+
+#[test]
+fn call_with_passes_a_str_to_get_property() {
+    common::with_jvm(|env| {
+        let class = env.find_class("java/lang/System").unwrap();
+        let get_property = SpStaticMethod::new(
+            4356,
+            "getProperty",
+            &SpType::new_class("java/lang/String"),
+            &[SpType::new_class("java/lang/String")],
+        );
+        get_property.init(env, &class).unwrap();
+
+        let value = get_property
+            .call_with(env, &class, &["line.separator".into()], ReturnType::Object)
+            .unwrap()
+            .l()
+            .unwrap();
+        let value: String = env.get_string(&value.into()).unwrap().into();
+        assert_eq!(value, "\n");
+
+        let missing = get_property
+            .call_with(env, &class, &["sp.util.missing".into()], ReturnType::Object)
+            .unwrap()
+            .l()
+            .unwrap();
+        assert!(missing.is_null());
+    });
+}
+
+#[test]
+fn call_global_object_array_returns_split_strings() {
+    common::with_jvm(|env| {
+        let class = env.find_class("java/lang/String").unwrap();
+        let split = SpMethod::new(
+            4399,
+            "split",
+            &SpType::array_of(SpType::new_class("java/lang/String"), 1),
+            &[SpType::new_class("java/lang/String")],
+        );
+        split.init(env, &class).unwrap();
+
+        let text = env.new_string("a,b,c").unwrap();
+        let comma = env.new_string(",").unwrap();
+        let parts = split
+            .call_global_object_array(env, &text, &[JValue::Object(&comma).as_jni()])
+            .unwrap();
+        let parts: Vec<String> = parts
+            .iter()
+            .map(|part| {
+                env.get_string(<&JString>::from(part.as_obj()))
+                    .unwrap()
+                    .into()
+            })
+            .collect();
+        assert_eq!(parts, ["a", "b", "c"]);
+    });
+}
+
+#[test]
+fn resolve_overload_picks_the_existing_overload_and_matches_a_cached_key() {
+    common::with_jvm(|env| {
+        let class = env.find_class("java/lang/String").unwrap();
+        let string = || SpType::new_class("java/lang/String");
+        let index_of = SpMethod::resolve_overload(
+            env,
+            &class,
+            4400,
+            "indexOf",
+            &SpType::Int,
+            &[&[SpType::Long], &[string()]],
+        )
+        .unwrap();
+        let text = env.new_string("abc").unwrap();
+        let needle = env.new_string("c").unwrap();
+        let args = [JValue::Object(&needle).as_jni()];
+        assert_eq!(index_of.call_int(env, &text, &args).unwrap(), 2);
+
+        // cached now: the candidate matching the cached id is returned, not the first one
+        let cached = SpMethod::resolve_overload(
+            env,
+            &class,
+            4400,
+            "indexOf",
+            &SpType::Int,
+            &[&[SpType::Int], &[string()]],
+        )
+        .unwrap();
+        let found = cached
+            .call_nullable(
+                env,
+                &text,
+                &[Some(&needle)],
+                ReturnType::Primitive(Primitive::Int),
+            )
+            .unwrap();
+        assert_eq!(found.i().unwrap(), 2);
+
+        let err = SpMethod::resolve_overload(
+            env,
+            &class,
+            4400,
+            "indexOf",
+            &SpType::Int,
+            &[&[SpType::Int]],
+        )
+        .err()
+        .unwrap();
+        assert!(err.to_string().contains("key=4400 is cached"));
+        assert!(!env.exception_check().unwrap());
+    });
+}
+
+#[test]
+fn call_as_checks_the_instance_of_the_result() {
+    common::with_jvm(|env| {
+        let class = env.find_class("java/lang/Integer").unwrap();
+        let value_of = SpStaticMethod::new(
+            4357,
+            "valueOf",
+            &SpType::new_class("java/lang/Integer"),
+            &[SpType::Int],
+        );
+        value_of.init(env, &class).unwrap();
+        let mut number = SpClass::new(4358, "java/lang/Number");
+        number.init(env).unwrap();
+        let mut string = SpClass::new(4359, "java/lang/String");
+        string.init(env).unwrap();
+
+        let args = [JValue::Int(7).as_jni()];
+        let boxed: JObject = value_of.call_as(env, &class, &args, Some(&number)).unwrap();
+        assert!(env.is_instance_of(&boxed, "java/lang/Integer").unwrap());
+
+        let err = value_of
+            .call_as::<JString>(env, &class, &args, Some(&string))
+            .err()
+            .unwrap();
+        assert_eq!(err.to_string(), "returned object is not a java/lang/String");
+    });
+}
+
+#[test]
+fn call_owned_borrows_owned_arguments() {
+    common::with_jvm(|env| {
+        let class = env.find_class("java/lang/String").unwrap();
+        let region_matches = SpMethod::new(
+            4402,
+            "regionMatches",
+            &SpType::Boolean,
+            &[
+                SpType::Int,
+                SpType::new_class("java/lang/String"),
+                SpType::Int,
+                SpType::Int,
+            ],
+        );
+        region_matches.init(env, &class).unwrap();
+
+        let text = env.new_string("hello world").unwrap();
+        let other: JObject = env.new_string("world").unwrap().into();
+        let args = [
+            JValueOwned::Int(6),
+            JValueOwned::Object(other),
+            JValueOwned::Int(0),
+            JValueOwned::Int(5),
+        ];
+        let matches = region_matches
+            .call_owned(env, &text, &args, ReturnType::Primitive(Primitive::Boolean))
+            .unwrap();
+        assert!(matches.z().unwrap());
+        // the arguments are only borrowed, the string is still usable
+        let JValueOwned::Object(other) = &args[1] else {
+            unreachable!()
+        };
+        assert!(env.is_instance_of(other, "java/lang/String").unwrap());
+    });
+}
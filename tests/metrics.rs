@@ -0,0 +1,36 @@
+#![cfg(feature = "metrics")]
+
+mod common;
+
+use jni_sp_util::{SpMethod, SpStaticMethod, SpType, method_timings, static_method_timings};
+
+#[test]
+fn two_calls_are_recorded() {
+    common::with_jvm(|env| {
+        let class = env.find_class("java/lang/String").unwrap();
+        let length = SpMethod::new(4701, "length", &SpType::Int, &[]);
+        length.init(env, &class).unwrap();
+        let value_of = SpStaticMethod::new(
+            4701,
+            "valueOf",
+            &SpType::new_class("java/lang/String"),
+            &[SpType::Int],
+        );
+        value_of.init(env, &class).unwrap();
+
+        let text = env.new_string("text").unwrap();
+        for _ in 0..2 {
+            length.call_int(env, &text, &[]).unwrap();
+            value_of
+                .call_string(env, &class, &[jni::objects::JValue::Int(1).as_jni()])
+                .unwrap();
+        }
+
+        let stats = method_timings()[&4701];
+        assert_eq!(stats.calls, 2);
+        assert!(stats.total_nanos > 0);
+        let stats = static_method_timings()[&4701];
+        assert_eq!(stats.calls, 2);
+        assert!(stats.total_nanos > 0);
+    });
+}
@@ -0,0 +1,42 @@
+mod common;
+
+use jni::JNIEnv;
+use jni::NativeMethod;
+use jni::objects::{JClass, JValue};
+use jni::sys::jint;
+use jni_sp_util::unregister_natives;
+
+extern "system" fn twice(_env: JNIEnv, _class: JClass, value: jint) -> jint {
+    value * 2
+}
+
+#[test]
+fn unregistered_native_throws_unsatisfied_link_error() {
+    common::with_jvm(|env| {
+        let class = common::define(env, "Native");
+        let method = NativeMethod {
+            name: "twice".into(),
+            sig: "(I)I".into(),
+            fn_ptr: twice as *mut _,
+        };
+        env.register_native_methods(&class, &[method]).unwrap();
+        let value = env
+            .call_static_method(&class, "twice", "(I)I", &[JValue::Int(21)])
+            .unwrap()
+            .i()
+            .unwrap();
+        assert_eq!(value, 42);
+
+        unregister_natives(env, &class).unwrap();
+        assert!(
+            env.call_static_method(&class, "twice", "(I)I", &[JValue::Int(21)])
+                .is_err()
+        );
+        let exception = env.exception_occurred().unwrap();
+        env.exception_clear().unwrap();
+        assert!(
+            env.is_instance_of(&exception, "java/lang/UnsatisfiedLinkError")
+                .unwrap()
+        );
+    });
+}
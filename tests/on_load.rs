@@ -0,0 +1,30 @@
+mod common;
+
+use jni::sys::{JNI_ERR, JNI_VERSION_1_6, JavaVM, jint};
+use jni_sp_util::{SpClass, get_java_vm, jni_on_load};
+use std::ffi::c_void;
+
+jni_on_load! { preload: [(4751, "java/lang/String"), (4752, "java/util/ArrayList")] }
+
+// the exact signature the jvm looks up
+const _: unsafe extern "system" fn(*mut JavaVM, *mut c_void) -> jint = JNI_OnLoad;
+
+#[test]
+fn on_load_stores_the_vm_and_preloads_classes() {
+    common::with_jvm(|env| {
+        let vm = env.get_java_vm().unwrap().get_java_vm_pointer();
+        assert_eq!(
+            unsafe { JNI_OnLoad(vm, std::ptr::null_mut()) },
+            JNI_VERSION_1_6
+        );
+        assert!(get_java_vm().is_ok());
+        assert!(SpClass::contains_cache(4751));
+        assert!(SpClass::contains_cache(4752));
+
+        // a null vm is an error returned to `System.loadLibrary`, not a panic
+        assert_eq!(
+            unsafe { JNI_OnLoad(std::ptr::null_mut(), std::ptr::null_mut()) },
+            JNI_ERR
+        );
+    });
+}
@@ -0,0 +1,165 @@
+mod common;
+
+use jni::objects::JValue;
+use jni_sp_util::{
+    SpField, SpType, attach_handle, expose_as_direct_buffer, free_ptr, read_handle,
+    register_handle, release_handle, resolve_handle, to_ptr, to_ptr_checked, to_status,
+    to_status_checked, to_status_map, to_status_use, to_status_use_checked,
+};
+
+#[derive(Debug, PartialEq)]
+struct Marker;
+
+#[repr(align(64))]
+struct Aligned(u8);
+
+#[test]
+fn unit_struct_round_trip() {
+    let p = to_ptr(Marker);
+    assert_eq!(*to_status_use::<Marker>(p).unwrap(), Marker);
+    assert_eq!(*to_status::<Marker>(p).unwrap(), Marker);
+}
+
+#[test]
+fn misaligned_pointer_is_an_error() {
+    let p = to_ptr(Aligned(3));
+    let forged = p + 8;
+    let err = to_status_use::<Aligned>(forged).err().unwrap();
+    assert!(err.to_string().contains("not aligned"));
+    assert_eq!(to_status_use::<Aligned>(p).unwrap().0, 3);
+    free_ptr::<Aligned>(p).unwrap();
+}
+
+#[test]
+fn status_map_mutates_and_returns_a_derived_value() {
+    let p = to_ptr(vec![1, 2]);
+    let len = to_status_map(p, |v: &mut Vec<i32>| {
+        v.push(3);
+        v.len()
+    })
+    .unwrap();
+    assert_eq!(len, 3);
+    assert_eq!(*to_status::<Vec<i32>>(p).unwrap(), [1, 2, 3]);
+}
+
+#[test]
+fn checked_pointer_round_trip() {
+    let p = to_ptr_checked(String::from("value"));
+    to_status_use_checked::<String>(p).unwrap().push('!');
+    assert_eq!(to_status_checked::<String>(p).unwrap(), "value!");
+}
+
+#[test]
+fn checked_pointer_rejects_other_type() {
+    let p = to_ptr_checked(7u64);
+    assert!(to_status_use_checked::<String>(p).is_err());
+    assert!(to_status_checked::<u32>(p).is_err());
+    // the mismatch left the value alone
+    assert_eq!(to_status_checked::<u64>(p).unwrap(), 7);
+}
+
+#[test]
+fn checked_pointer_rejects_freed_and_unchecked() {
+    let p = to_ptr_checked(1i32);
+    to_status_checked::<i32>(p).unwrap();
+    assert!(to_status_use_checked::<i32>(p).is_err());
+    assert!(to_status_checked::<i32>(p).is_err());
+
+    let unchecked = to_ptr(2i32);
+    assert!(to_status_use_checked::<i32>(unchecked).is_err());
+    free_ptr::<i32>(unchecked).unwrap();
+}
+
+#[test]
+fn double_free_is_an_error() {
+    let p = to_ptr(String::from("value"));
+    free_ptr::<String>(p).unwrap();
+    assert!(free_ptr::<String>(p).is_err());
+
+    let p = to_ptr(vec![1u8, 2, 3]);
+    assert_eq!(*to_status::<Vec<u8>>(p).unwrap(), [1, 2, 3]);
+    assert!(to_status::<Vec<u8>>(p).is_err());
+    assert!(free_ptr::<Vec<u8>>(p).is_err());
+}
+
+#[test]
+fn handle_resolves_while_registered() {
+    let id = register_handle(String::from("value"));
+    unsafe { resolve_handle::<String>(id) }.unwrap().push('!');
+    assert_eq!(unsafe { resolve_handle::<String>(id) }.unwrap(), "value!");
+    assert!(unsafe { resolve_handle::<u32>(id) }.is_err());
+    assert_eq!(release_handle::<String>(id).unwrap(), "value!");
+}
+
+#[test]
+fn released_handle_is_stale() {
+    let id = register_handle(1u32);
+    release_handle::<u32>(id).unwrap();
+    assert!(unsafe { resolve_handle::<u32>(id) }.is_err());
+    assert!(release_handle::<u32>(id).is_err());
+
+    // the slot is reused under a new generation, the old id still does not reach it
+    let reused = register_handle(2u32);
+    assert_ne!(reused, id);
+    assert!(unsafe { resolve_handle::<u32>(id) }.is_err());
+    assert_eq!(release_handle::<u32>(reused).unwrap(), 2);
+}
+
+#[test]
+fn out_of_range_handle_is_an_error() {
+    let forged = (1i64 << 32) | 0xffff_fff0;
+    assert!(unsafe { resolve_handle::<u32>(forged) }.is_err());
+    assert!(release_handle::<u32>(forged).is_err());
+    assert!(unsafe { resolve_handle::<u32>(0) }.is_err());
+}
+
+#[test]
+fn handle_round_trips_through_a_long_field() {
+    common::with_jvm(|env| {
+        let class = common::define(env, "Fields");
+        let total = SpField::new(4801, "total", &SpType::Long);
+        total.init(env, &class).unwrap();
+        let obj = env.new_object(&class, "()V", &[]).unwrap();
+
+        let p = to_ptr(vec![1, 2, 3]);
+        attach_handle(env, &obj, &total, p).unwrap();
+        read_handle::<Vec<i32>>(env, &obj, &total).unwrap().push(4);
+        assert_eq!(
+            *read_handle::<Vec<i32>>(env, &obj, &total).unwrap(),
+            [1, 2, 3, 4]
+        );
+        assert_eq!(
+            env.get_field(&obj, "total", "J").unwrap().j().unwrap(),
+            p as i64
+        );
+        free_ptr::<Vec<i32>>(p).unwrap();
+    });
+}
+
+#[test]
+fn exposed_vec_is_read_and_written_by_java() {
+    common::with_jvm(|env| {
+        let p = to_ptr(vec![1u8, 2, 3]);
+        let data = to_status_use::<Vec<u8>>(p).unwrap();
+        let buffer = unsafe { expose_as_direct_buffer(env, data) }.unwrap();
+
+        let at = |env: &mut jni::JNIEnv, i: i32| {
+            env.call_method(&buffer, "get", "(I)B", &[JValue::Int(i)])
+                .unwrap()
+                .b()
+                .unwrap()
+        };
+        assert_eq!([at(env, 0), at(env, 1), at(env, 2)], [1, 2, 3]);
+
+        env.call_method(
+            &buffer,
+            "put",
+            "(IB)Ljava/nio/ByteBuffer;",
+            &[JValue::Int(1), JValue::Byte(9)],
+        )
+        .unwrap();
+        assert_eq!(*to_status_use::<Vec<u8>>(p).unwrap(), [1, 9, 3]);
+        env.delete_local_ref(buffer).unwrap();
+        free_ptr::<Vec<u8>>(p).unwrap();
+    });
+}
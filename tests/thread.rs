@@ -0,0 +1,85 @@
+mod common;
+
+use jni::JNIEnv;
+use jni::objects::JObject;
+use jni_sp_util::{JniExecutor, anyhow, spawn_with_callback};
+use std::sync::{Arc, Mutex};
+
+fn field<'local>(env: &mut JNIEnv<'local>, obj: &JObject, name: &str) -> JObject<'local> {
+    env.get_field(obj, name, "Ljava/lang/Object;")
+        .unwrap()
+        .l()
+        .unwrap()
+}
+
+#[test]
+fn spawn_with_callback_reports_success_and_error() {
+    common::with_jvm(|env| {
+        let class = common::define(env, "Recorder");
+        let recorder = env.new_object(&class, "()V", &[]).unwrap();
+        let callback = env.new_global_ref(&recorder).unwrap();
+
+        let handle = spawn_with_callback(env, callback.clone(), |env| {
+            Ok(env.new_string("done")?.into())
+        })
+        .unwrap();
+        handle.join().unwrap().unwrap();
+        assert_eq!(
+            env.get_field(&recorder, "calls", "I").unwrap().i().unwrap(),
+            1
+        );
+        let result = field(env, &recorder, "result");
+        let result: String = env.get_string(&result.into()).unwrap().into();
+        assert_eq!(result, "done");
+        assert!(field(env, &recorder, "error").is_null());
+
+        let handle = spawn_with_callback(env, callback, |_| Err(anyhow!("failed"))).unwrap();
+        handle.join().unwrap().unwrap();
+        assert_eq!(
+            env.get_field(&recorder, "calls", "I").unwrap().i().unwrap(),
+            2
+        );
+        assert!(field(env, &recorder, "result").is_null());
+        let error = field(env, &recorder, "error");
+        assert!(
+            env.is_instance_of(&error, "java/lang/RuntimeException")
+                .unwrap()
+        );
+        let message = env
+            .call_method(&error, "getMessage", "()Ljava/lang/String;", &[])
+            .unwrap()
+            .l()
+            .unwrap();
+        let message: String = env.get_string(&message.into()).unwrap().into();
+        assert!(message.contains("failed"));
+    });
+}
+
+#[test]
+fn executor_runs_tasks_in_order() {
+    if common::vm().is_none() {
+        eprintln!("no jdk found, skipping");
+        return;
+    }
+    let executor = JniExecutor::new().unwrap();
+    let order = Arc::new(Mutex::new(Vec::new()));
+    let receivers: Vec<_> = ["first", "second!"]
+        .into_iter()
+        .map(|text| {
+            let order = order.clone();
+            executor
+                .submit(move |env| {
+                    order.lock().unwrap().push(text);
+                    let string = env.new_string(text)?;
+                    Ok(env.call_method(&string, "length", "()I", &[])?.i()?)
+                })
+                .unwrap()
+        })
+        .collect();
+    let results: Vec<i32> = receivers
+        .into_iter()
+        .map(|receiver| receiver.recv().unwrap().unwrap())
+        .collect();
+    assert_eq!(results, [5, 7]);
+    assert_eq!(*order.lock().unwrap(), ["first", "second!"]);
+}
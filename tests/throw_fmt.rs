@@ -0,0 +1,55 @@
+use jni_sp_util::{Result, throw_fmt};
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+
+/// counts the allocations of the current thread, other test threads do not interfere
+struct CountingAlloc;
+
+thread_local! {
+    static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+unsafe impl GlobalAlloc for CountingAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.with(|n| n.set(n.get() + 1));
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { System.dealloc(ptr, layout) }
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAlloc = CountingAlloc;
+
+fn allocations() -> usize {
+    ALLOCATIONS.with(Cell::get)
+}
+
+fn check_index(index: usize, len: usize) -> Result<usize> {
+    if index >= len {
+        return throw_fmt!("index out of bounds: {} >= {}", index, len);
+    }
+    Ok(index)
+}
+
+#[test]
+fn throw_fmt_formats_the_message() {
+    let err = check_index(5, 3).err().unwrap();
+    assert_eq!(err.to_string(), "index out of bounds: 5 >= 3");
+}
+
+#[test]
+fn throw_fmt_success_path_does_not_allocate() {
+    let before = allocations();
+    let mut sum = 0;
+    for i in 0..1000 {
+        sum += check_index(i, 1000).unwrap();
+    }
+    assert_eq!(allocations(), before);
+    assert_eq!(sum, 499_500);
+
+    check_index(1000, 1000).err().unwrap();
+    assert!(allocations() > before);
+}
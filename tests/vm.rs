@@ -0,0 +1,18 @@
+use jni_sp_util::{SpError, with_env};
+
+// this binary never sets a java vm
+#[test]
+fn with_env_without_vm_is_attach_failed() {
+    let mut ran = false;
+    let err = with_env(|_| {
+        ran = true;
+        Ok(())
+    })
+    .err()
+    .unwrap();
+    assert!(!ran);
+    assert!(matches!(
+        err.downcast_ref::<SpError>(),
+        Some(SpError::AttachFailed(_))
+    ));
+}